@@ -0,0 +1,32 @@
+//! Read only access to the parsed syntax tree of a script, for building tools like linters on top
+//! of nash.
+
+pub use crate::components::{
+    AccessorExpression, ArrayExpression, Assignment, BaseExpression, Block, BlockExpression,
+    BooleanLiteral, BracketExpression, BranchExpression, CharLiteral, CommandLiteral,
+    DefaultExpression, DoWhileLoopExpression, Expression, FloatLiteral, ForLoopExpression,
+    Function, Identifier, IndexExpression, IntegerLiteral, MapExpression, MatchExpression,
+    NegationExpression, NotExpression, Operator, PipelineExpression, RangeExpression, Root,
+    Statement, StringLiteral, TupleExpression, TypeDefinition, VariableExpression,
+    WhileLoopExpression,
+};
+use crate::NashError;
+
+/// Callbacks for each kind of node found while walking a syntax tree, nodes are visited before
+/// any of the nodes nested inside them.
+pub trait Visitor {
+    fn visit_function(&mut self, _function: &Function) {}
+    fn visit_statement(&mut self, _statement: &Statement) {}
+    fn visit_expression(&mut self, _expression: &BaseExpression) {}
+}
+
+/// Parses a script without type checking or running it, the same as `parse_script` but only
+/// keeping the syntax tree.
+pub fn parse(script: &str) -> Result<Root, NashError> {
+    Ok(crate::parse_script(script)?.into_root())
+}
+
+/// Walks every function, statement and expression in the tree, starting with the functions.
+pub fn walk(root: &Root, visitor: &mut dyn Visitor) {
+    root.walk(visitor);
+}
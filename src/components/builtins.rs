@@ -1,17 +1,110 @@
-use super::values::{FileMode, Type, Value};
-use crate::{errors::ExecutionError, Executor};
-use std::{cell::RefCell, io::BufRead, io::Write};
+use super::{
+    json,
+    stack::Stack,
+    values::{FileMode, Float, Type, Value},
+    EvaluationResult, Operator, TypeDefinition,
+};
+use crate::{
+    errors::ExecutionError,
+    utils::{formatting, iterators::is_duplicates},
+    CommandDefinition, Executor, Pipeline, PipelineOutput, PipelineSource,
+};
+use std::{
+    cell::RefCell, cmp::Ordering, collections::HashMap, env, io::BufRead, io::Read, io::Write,
+    path::Path, str::FromStr, time::Instant,
+};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 pub fn get_builtin_type(name: &str, args: &[Type]) -> Option<Type> {
     Some(match (name, args) {
         ("parse_int", [Type::String]) => Type::Integer,
+        ("parse_size", [Type::String]) => Type::Integer,
+        ("parse_float", [Type::String]) => Type::Float,
+        ("char_from_int", [Type::Integer]) => Type::Char,
+        ("char_to_int", [Type::Char]) => Type::Integer,
         ("read", []) => Type::FileHandle,
+        ("read_raw", []) => Type::String,
+        ("read_all", []) => Type::String,
         ("open", [Type::String]) => Type::FileHandle,
         ("write", [Type::String]) => Type::FileHandle,
         ("append", [Type::String]) => Type::FileHandle,
+        ("tee", [Type::String]) => Type::FileHandle,
         ("err", [Type::String]) => Type::Void,
         ("out", [Type::String]) => Type::Void,
         ("glob", [Type::String]) => Type::Array(Box::new(Type::String), false),
+        ("glob", [Type::String, Type::Boolean]) => Type::Array(Box::new(Type::String), false),
+        ("glob", [Type::String, Type::Boolean, Type::Boolean]) => {
+            Type::Array(Box::new(Type::String), false)
+        }
+        ("list_dir", [Type::String]) => Type::Array(Box::new(Type::String), false),
+        ("list_dir", [Type::String, Type::Boolean]) => Type::Array(Box::new(Type::String), false),
+        ("is_dir", [Type::String]) => Type::Boolean,
+        ("is_file", [Type::String]) => Type::Boolean,
+        ("env", [Type::String]) => Type::String,
+        ("set_env", [Type::String, Type::String]) => Type::Void,
+        ("args", []) => Type::Array(Box::new(Type::String), false),
+        ("read_file", [Type::String]) => Type::String,
+        ("read_lines", [Type::String]) => Type::Array(Box::new(Type::String), false),
+        ("write_file", [Type::String, Type::String]) => Type::Void,
+        ("url_encode", [Type::String]) => Type::String,
+        ("url_decode", [Type::String]) => Type::String,
+        ("parse_json", [Type::String]) => Type::Json,
+        ("assert_type", [value, Type::String]) => value.clone(),
+        ("assert", [Type::Boolean]) => Type::Void,
+        ("assert_eq", [left, right]) => {
+            if left != right
+                || Operator::Equal
+                    .get_type(left.clone(), right.clone())
+                    .is_err()
+            {
+                return None;
+            }
+            Type::Void
+        }
+        // Calls to `default` with a type as the argument are handled by the default expression
+        ("default", [value, fallback]) => {
+            if value != fallback && *value != Type::Void {
+                return None;
+            }
+            fallback.clone()
+        }
+        // Json values are currently the only values that can be null
+        ("coalesce", args) if !args.is_empty() && args.iter().all(|arg| *arg == Type::Json) => {
+            Type::Json
+        }
+        ("to_json", [value]) => {
+            if !value.is_json_compatible() {
+                return None;
+            }
+            Type::String
+        }
+        ("format_map", [Type::String, Type::Map(value_type, _)]) => {
+            if **value_type != Type::String {
+                return None;
+            }
+            Type::String
+        }
+        ("format_map", [Type::String, Type::Map(value_type, _), Type::Boolean]) => {
+            if **value_type != Type::String {
+                return None;
+            }
+            Type::String
+        }
+        ("abs", [Type::Integer]) => Type::Integer,
+        ("min", [Type::Integer, Type::Integer]) => Type::Integer,
+        ("max", [Type::Integer, Type::Integer]) => Type::Integer,
+        ("pow", [Type::Integer, Type::Integer]) => Type::Integer,
+        ("abs", [Type::Float]) => Type::Float,
+        ("min", [Type::Float, Type::Float]) => Type::Float,
+        ("max", [Type::Float, Type::Float]) => Type::Float,
+        ("pow", [Type::Float, Type::Float]) => Type::Float,
+        ("compare", [left, right]) => {
+            if left != right || !matches!(left, Type::Integer | Type::String | Type::Boolean) {
+                return None;
+            }
+            Type::Integer
+        }
         _ => return None,
     })
 }
@@ -19,20 +112,198 @@ pub fn get_builtin_type(name: &str, args: &[Type]) -> Option<Type> {
 pub fn get_builtin_instance_type(name: &str, instance: Type, args: &[Type]) -> Option<Type> {
     Some(match (name, instance, args) {
         ("fmt", _, []) => Type::String,
+        ("fmt", _, [Type::String]) => Type::String,
+        ("str", _, []) => Type::String,
         ("push", Type::Array(inner_type, true), [value]) => {
-            if *value != *inner_type {
+            if !value.is_assignable_to(&inner_type) {
                 return None;
             };
             Type::Void
         }
         ("pop", Type::Array(inner_type, true), []) => *inner_type,
+        ("insert", Type::Array(inner_type, true), [Type::Integer, value]) => {
+            if !value.is_assignable_to(&inner_type) {
+                return None;
+            };
+            Type::Void
+        }
+        ("remove", Type::Array(inner_type, true), [Type::Integer]) => *inner_type,
+        ("clear", Type::Array(_, true), []) => Type::Void,
         ("len", Type::Array(_, _), []) => Type::Integer,
         ("len", Type::String, []) => Type::Integer,
+        ("display_width", Type::String, []) => Type::Integer,
+        ("len", Type::Map(_, _), []) => Type::Integer,
+        ("len", Type::Tuple(_), []) => Type::Integer,
+        ("to_array", Type::Tuple(item_types), []) => {
+            Type::Array(Box::new(is_duplicates(item_types)?), false)
+        }
+        ("windows", Type::Array(inner_type, _), [Type::Integer]) => {
+            Type::Array(Box::new(Type::Array(inner_type, false)), false)
+        }
+        ("pairs", Type::Array(inner_type, _), []) => {
+            Type::Array(Box::new(Type::Array(inner_type, false)), false)
+        }
+        ("contains", Type::Array(inner_type, _), [value]) => {
+            if *value != *inner_type {
+                return None;
+            };
+            Type::Boolean
+        }
+        ("index_of", Type::Array(inner_type, _), [value]) => {
+            if *value != *inner_type {
+                return None;
+            };
+            Type::Integer
+        }
+        ("positions", Type::Array(inner_type, _), [value]) => {
+            if *value != *inner_type {
+                return None;
+            };
+            Type::Array(Box::new(Type::Integer), false)
+        }
+        ("reverse", Type::Array(inner_type, _), []) => Type::Array(inner_type, false),
+        ("sum" | "min" | "max", Type::Array(inner_type, _), []) => {
+            if *inner_type != Type::Integer {
+                return None;
+            }
+            Type::Integer
+        }
+        ("slice", Type::Array(inner_type, _), [Type::Integer, Type::Integer]) => {
+            Type::Array(inner_type, false)
+        }
+        ("slice", Type::String, [Type::Integer, Type::Integer]) => Type::String,
+        ("sort", Type::Array(inner_type, mutable), []) => {
+            if !matches!(*inner_type, Type::Integer | Type::String) {
+                return None;
+            }
+            Type::Array(inner_type, mutable)
+        }
+        ("is_empty", Type::String | Type::Array(_, _) | Type::Tuple(_), []) => Type::Boolean,
+        ("as_string", Type::Json, []) => Type::String,
+        ("as_integer", Type::Json, []) => Type::Integer,
+        ("as_float", Type::Json, []) => Type::Float,
+        ("as_boolean", Type::Json, []) => Type::Boolean,
+        ("as_array", Type::Json, []) => Type::Array(Box::new(Type::Json), false),
+        ("as_map", Type::Json, []) => Type::Map(Box::new(Type::Json), false),
+        ("is_null", Type::Json, []) => Type::Boolean,
+        ("get", Type::Map(inner_type, _), [Type::String]) => *inner_type,
+        ("set", Type::Map(inner_type, true), [Type::String, value]) => {
+            if !value.is_assignable_to(&inner_type) {
+                return None;
+            };
+            Type::Void
+        }
+        ("has", Type::Map(_, _), [Type::String]) => Type::Boolean,
+        ("keys", Type::Map(_, _), []) => Type::Array(Box::new(Type::String), false),
+        ("remove", Type::Map(inner_type, true), [Type::String]) => *inner_type,
+        ("run_with_input", Type::Command, [Type::String]) => Type::String,
         ("ends_with", Type::String, [Type::String]) => Type::Boolean,
+        ("starts_with", Type::String, [Type::String]) => Type::Boolean,
+        ("contains", Type::String, [Type::String]) => Type::Boolean,
+        ("index_of", Type::String, [Type::String]) => Type::Integer,
+        ("split", Type::String, [Type::String]) => Type::Array(Box::new(Type::String), false),
+        ("chars", Type::String, []) => Type::Array(Box::new(Type::String), false),
+        ("split_at", Type::String, [Type::Integer]) => {
+            Type::Tuple(vec![Type::String, Type::String])
+        }
+        ("trim", Type::String, []) => Type::String,
+        ("number_lines", Type::String, []) => Type::String,
+        ("replace", Type::String, [Type::String, Type::String]) => Type::String,
+        ("replace_first", Type::String, [Type::String, Type::String]) => Type::String,
+        ("replace_n", Type::String, [Type::String, Type::String, Type::Integer]) => Type::String,
+        ("to_upper", Type::String, []) => Type::String,
+        ("to_binary", Type::Integer, []) => Type::String,
+        ("to_hex", Type::Integer, []) => Type::String,
+        ("count_ones", Type::Integer, []) => Type::Integer,
+        ("leading_zeros", Type::Integer, []) => Type::Integer,
+        ("repeat", Type::String, [Type::Integer]) => Type::String,
+        ("wrap", Type::String, [Type::Integer]) => Type::String,
+        ("join", Type::Array(inner_type, _), [Type::String]) => {
+            if *inner_type != Type::String {
+                return None;
+            }
+            Type::String
+        }
+        ("to_lower", Type::String, []) => Type::String,
+        ("line_count", Type::String, []) => Type::Integer,
+        ("word_count", Type::String, []) => Type::Integer,
+        ("map", Type::Array(inner_type, _), [Type::Function(arguments, return_type)]) => {
+            if *arguments != [*inner_type] || **return_type == Type::Void {
+                return None;
+            }
+            Type::Array(return_type.clone(), false)
+        }
+        ("filter", Type::Array(inner_type, _), [Type::Function(arguments, return_type)]) => {
+            if *arguments != [*inner_type.clone()] || **return_type != Type::Boolean {
+                return None;
+            }
+            Type::Array(inner_type, false)
+        }
+        (
+            "positions_where",
+            Type::Array(inner_type, _),
+            [Type::Function(arguments, return_type)],
+        ) => {
+            if *arguments != [*inner_type] || **return_type != Type::Boolean {
+                return None;
+            }
+            Type::Array(Box::new(Type::Integer), false)
+        }
+        ("sort_by", Type::Array(inner_type, mutable), [Type::Function(arguments, return_type)]) => {
+            if *arguments != [*inner_type.clone(), *inner_type.clone()]
+                || **return_type != Type::Integer
+            {
+                return None;
+            }
+            Type::Array(inner_type, mutable)
+        }
+        ("each", Type::Array(inner_type, _), [Type::Function(arguments, return_type)]) => {
+            if *arguments != [*inner_type] || **return_type != Type::Void {
+                return None;
+            }
+            Type::Void
+        }
+        ("partition", Type::Array(inner_type, _), [Type::Function(arguments, return_type)]) => {
+            if *arguments != [*inner_type.clone()] || **return_type != Type::Boolean {
+                return None;
+            }
+            Type::Tuple(vec![
+                Type::Array(inner_type.clone(), false),
+                Type::Array(inner_type, false),
+            ])
+        }
+        (
+            "reduce",
+            Type::Array(inner_type, _),
+            [Type::Function(arguments, return_type), initial],
+        ) => {
+            if *arguments != [initial.clone(), *inner_type] || **return_type != *initial {
+                return None;
+            }
+            initial.clone()
+        }
         _ => return None,
     })
 }
 
+/// The zero value for a type, like rust's `Default`. Collections are empty but keep the
+/// mutability of the type they are created for.
+pub fn default_value(value_type: &Type) -> Result<Value, ExecutionError> {
+    match value_type {
+        Type::String => Ok(String::new().into()),
+        Type::Integer => Ok(0.into()),
+        Type::Float => Ok(Float::new(0.0).expect("Zero is finite").into()),
+        Type::Boolean => Ok(false.into()),
+        Type::Array(inner_type, mutable) => {
+            Value::new_array(Vec::<Value>::new(), *inner_type.clone(), *mutable)
+        }
+        Type::Map(inner_type, mutable) => {
+            Value::new_map(Vec::<(String, Value)>::new(), *inner_type.clone(), *mutable)
+        }
+        value_type => Err(format!("Type {value_type} does not have a default value").into()),
+    }
+}
+
 pub fn call_builtin<E: Executor>(
     name: &str,
     args: &[Value],
@@ -40,13 +311,66 @@ pub fn call_builtin<E: Executor>(
 ) -> Result<Value, ExecutionError> {
     match (name, args) {
         ("parse_int", [Value::String(arg1)]) => Ok(parse_int(executor, arg1)?),
+        ("parse_size", [Value::String(arg1)]) => parse_size(executor, arg1),
+        ("parse_float", [Value::String(arg1)]) => parse_float(executor, arg1),
+        ("char_from_int", [Value::Integer(arg1)]) => char_from_int(executor, *arg1),
+        ("char_to_int", [Value::Char(arg1)]) => Ok(Value::Integer(*arg1 as i32)),
         ("read", []) => read(executor),
+        ("read_raw", []) => read_raw(executor),
+        ("read_all", []) => read_all(executor),
         ("open", [Value::String(arg1)]) => open(executor, arg1),
         ("write", [Value::String(arg1)]) => write(executor, arg1),
         ("append", [Value::String(arg1)]) => append(executor, arg1),
+        ("tee", [Value::String(arg1)]) => tee(executor, arg1),
         ("err", [Value::String(arg1)]) => err(executor, arg1),
         ("out", [Value::String(arg1)]) => out(executor, arg1),
-        ("glob", [Value::String(arg1)]) => glob(executor, arg1),
+        ("glob", [Value::String(arg1)]) => glob(executor, arg1, false, false),
+        ("glob", [Value::String(arg1), Value::Boolean(arg2)]) => glob(executor, arg1, *arg2, false),
+        ("glob", [Value::String(arg1), Value::Boolean(arg2), Value::Boolean(arg3)]) => {
+            glob(executor, arg1, *arg2, *arg3)
+        }
+        ("list_dir", [Value::String(arg1)]) => list_dir(executor, arg1, false),
+        ("list_dir", [Value::String(arg1), Value::Boolean(arg2)]) => {
+            list_dir(executor, arg1, *arg2)
+        }
+        ("is_dir", [Value::String(arg1)]) => Ok(executor.is_dir(arg1).into()),
+        ("is_file", [Value::String(arg1)]) => Ok(executor.is_file(arg1).into()),
+        ("env", [Value::String(arg1)]) => env(executor, arg1),
+        ("set_env", [Value::String(arg1), Value::String(arg2)]) => set_env(executor, arg1, arg2),
+        ("args", []) => script_args(executor),
+        ("read_file", [Value::String(arg1)]) => read_file(executor, arg1),
+        ("read_lines", [Value::String(arg1)]) => read_lines(executor, arg1),
+        ("write_file", [Value::String(arg1), Value::String(arg2)]) => {
+            write_file(executor, arg1, arg2)
+        }
+        ("url_encode", [Value::String(arg1)]) => url_encode(executor, arg1),
+        ("url_decode", [Value::String(arg1)]) => url_decode(executor, arg1),
+        ("parse_json", [Value::String(arg1)]) => json::parse(arg1),
+        ("assert_type", [arg1, Value::String(arg2)]) => assert_type(executor, arg1, arg2),
+        ("assert", [Value::Boolean(arg1)]) => assert(executor, *arg1),
+        ("assert_eq", [arg1, arg2]) => assert_eq(executor, arg1, arg2),
+        ("coalesce", args) if !args.is_empty() => coalesce(executor, args),
+        ("default", [arg1, arg2]) => default(executor, arg1, arg2),
+        ("to_json", [arg1]) => Ok(json::serialize(arg1)?.into()),
+        ("format_map", [Value::String(arg1), Value::Map(arg2, _, _)]) => {
+            format_map(executor, arg1, &arg2.borrow(), false)
+        }
+        ("format_map", [Value::String(arg1), Value::Map(arg2, _, _), Value::Boolean(arg3)]) => {
+            format_map(executor, arg1, &arg2.borrow(), *arg3)
+        }
+        ("abs", [Value::Integer(arg1)]) => abs(executor, *arg1),
+        ("min", [Value::Integer(arg1), Value::Integer(arg2)]) => Ok((*arg1.min(arg2)).into()),
+        ("max", [Value::Integer(arg1), Value::Integer(arg2)]) => Ok((*arg1.max(arg2)).into()),
+        ("pow", [Value::Integer(arg1), Value::Integer(arg2)]) => pow(executor, *arg1, *arg2),
+        ("abs", [Value::Float(arg1)]) => Ok(Float::new(arg1.value().abs()).unwrap().into()),
+        ("min", [Value::Float(arg1), Value::Float(arg2)]) => {
+            Ok(Float::new(arg1.value().min(arg2.value())).unwrap().into())
+        }
+        ("max", [Value::Float(arg1), Value::Float(arg2)]) => {
+            Ok(Float::new(arg1.value().max(arg2.value())).unwrap().into())
+        }
+        ("pow", [Value::Float(arg1), Value::Float(arg2)]) => pow_float(executor, *arg1, *arg2),
+        ("compare", [arg1, arg2]) => compare(executor, arg1, arg2),
         (name, args) => {
             let args = args
                 .iter()
@@ -64,12 +388,15 @@ pub fn call_builtin_instance<E: Executor>(
     name: &str,
     instance: &Value,
     args: &[Value],
+    stack: &mut Stack,
     executor: &mut E,
-) -> Result<Value, ExecutionError> {
-    match (name, instance, args) {
+) -> EvaluationResult<Value> {
+    let result = match (name, instance, args) {
         ("fmt", instance, []) => fmt(executor, instance),
+        ("fmt", instance, [Value::String(spec)]) => fmt_with_spec(executor, instance, spec),
+        ("str", instance, []) => str(executor, instance),
         ("push", Value::Array(instance, array_type, true), [value]) => {
-            if array_type != &value.get_type() {
+            if !value.get_type().is_assignable_to(array_type) {
                 return Err(format!(
                     "Can not push a value of type {} to an array with type {}",
                     value.get_type(),
@@ -80,11 +407,191 @@ pub fn call_builtin_instance<E: Executor>(
             push(executor, instance.as_ref(), value)
         }
         ("pop", Value::Array(instance, _, true), []) => pop(executor, instance.as_ref()),
+        ("insert", Value::Array(instance, array_type, true), [Value::Integer(index), value]) => {
+            if !value.get_type().is_assignable_to(array_type) {
+                return Err(format!(
+                    "Can not insert a value of type {} into an array with type {}",
+                    value.get_type(),
+                    array_type,
+                )
+                .into());
+            }
+            array_insert(executor, instance.as_ref(), *index, value)
+        }
+        ("remove", Value::Array(instance, _, true), [Value::Integer(index)]) => {
+            array_remove(executor, instance.as_ref(), *index)
+        }
+        ("clear", Value::Array(instance, _, true), []) => array_clear(executor, instance.as_ref()),
         ("len", Value::Array(instance, _, _), []) => array_len(executor, instance.as_ref()),
         ("len", Value::String(instance), []) => string_len(executor, instance),
+        ("display_width", Value::String(instance), []) => string_display_width(executor, instance),
+        ("len", Value::Map(instance, _, _), []) => map_len(executor, instance.as_ref()),
+        ("len", Value::Tuple(instance), []) => tuple_len(executor, instance),
+        ("to_array", Value::Tuple(instance), []) => tuple_to_array(executor, instance),
+        ("to_tuple", Value::Array(instance, _, _), [Value::Integer(length)]) => {
+            array_to_tuple(executor, instance.as_ref(), *length)
+        }
+        ("windows", Value::Array(instance, array_type, _), [Value::Integer(size)]) => {
+            array_windows(executor, instance.as_ref(), array_type, *size)
+        }
+        ("pairs", Value::Array(instance, array_type, _), []) => {
+            array_windows(executor, instance.as_ref(), array_type, 2)
+        }
+        ("contains", Value::Array(instance, _, _), [value]) => {
+            Ok(instance.borrow().contains(value).into())
+        }
+        ("index_of", Value::Array(instance, _, _), [value]) => {
+            array_index_of(executor, instance.as_ref(), value)
+        }
+        ("positions", Value::Array(instance, _, _), [value]) => {
+            array_positions(executor, instance.as_ref(), value)
+        }
+        ("reverse", Value::Array(instance, array_type, _), []) => {
+            array_reverse(executor, instance.as_ref(), array_type)
+        }
+        ("sum", Value::Array(instance, Type::Integer, _), []) => {
+            array_sum(executor, instance.as_ref())
+        }
+        ("min", Value::Array(instance, Type::Integer, _), []) => {
+            array_min_max(executor, instance.as_ref(), "min", i32::min)
+        }
+        ("max", Value::Array(instance, Type::Integer, _), []) => {
+            array_min_max(executor, instance.as_ref(), "max", i32::max)
+        }
+        (
+            "slice",
+            Value::Array(instance, array_type, _),
+            [Value::Integer(start), Value::Integer(end)],
+        ) => array_slice(executor, instance.as_ref(), array_type, *start, *end),
+        ("slice", Value::String(instance), [Value::Integer(start), Value::Integer(end)]) => {
+            string_slice(executor, instance, *start, *end)
+        }
+        ("sort", Value::Array(instance, array_type, mutable), []) => {
+            array_sort(executor, instance.as_ref(), array_type, *mutable)
+        }
+        ("is_empty", instance, []) => is_empty(executor, instance),
+        ("as_string", Value::String(_), []) => Ok(instance.clone()),
+        ("as_integer", Value::Integer(_), []) => Ok(instance.clone()),
+        ("as_float", Value::Float(_), []) => Ok(instance.clone()),
+        ("as_boolean", Value::Boolean(_), []) => Ok(instance.clone()),
+        ("as_array", Value::Array(_, _, _), []) => Ok(instance.clone()),
+        ("as_map", Value::Map(_, _, _), []) => Ok(instance.clone()),
+        (
+            "as_string" | "as_integer" | "as_float" | "as_boolean" | "as_array" | "as_map",
+            instance,
+            [],
+        ) => Err(format!("Unable to convert json value {instance} using {name}").into()),
+        ("is_null", instance, []) => Ok((*instance == Value::Null).into()),
+        ("get", Value::Map(instance, _, _), [Value::String(key)]) => {
+            map_get(executor, instance.as_ref(), key)
+        }
+        ("set", Value::Map(instance, map_type, true), [Value::String(key), value]) => {
+            if !value.get_type().is_assignable_to(map_type) {
+                return Err(format!(
+                    "Can not set a value of type {} in a map with type {}",
+                    value.get_type(),
+                    map_type,
+                )
+                .into());
+            }
+            map_set(executor, instance.as_ref(), key, value)
+        }
+        ("has", Value::Map(instance, _, _), [Value::String(key)]) => {
+            map_has(executor, instance.as_ref(), key)
+        }
+        ("keys", Value::Map(instance, _, _), []) => map_keys(executor, instance.as_ref()),
+        ("remove", Value::Map(instance, _, true), [Value::String(key)]) => {
+            map_remove(executor, instance.as_ref(), key)
+        }
+        ("run_with_input", Value::Command(program, arguments), [Value::String(input)]) => {
+            run_with_input(executor, program, arguments, input)
+        }
         ("ends_with", Value::String(instance), [Value::String(value)]) => {
             ends_with(executor, instance, value)
         }
+        ("starts_with", Value::String(instance), [Value::String(value)]) => {
+            starts_with(executor, instance, value)
+        }
+        ("contains", Value::String(instance), [Value::String(value)]) => {
+            string_contains(executor, instance, value)
+        }
+        ("index_of", Value::String(instance), [Value::String(value)]) => {
+            string_index_of(executor, instance, value)
+        }
+        ("split", Value::String(instance), [Value::String(separator)]) => {
+            split(executor, instance, separator)
+        }
+        ("chars", Value::String(instance), []) => chars(executor, instance),
+        ("split_at", Value::String(instance), [Value::Integer(index)]) => {
+            split_at(executor, instance, *index)
+        }
+        ("trim", Value::String(instance), []) => trim(executor, instance),
+        ("number_lines", Value::String(instance), []) => number_lines(executor, instance),
+        ("replace", Value::String(instance), [Value::String(from), Value::String(to)]) => {
+            replace(executor, instance, from, to)
+        }
+        ("replace_first", Value::String(instance), [Value::String(from), Value::String(to)]) => {
+            replace_n(executor, instance, from, to, 1)
+        }
+        (
+            "replace_n",
+            Value::String(instance),
+            [Value::String(from), Value::String(to), Value::Integer(count)],
+        ) => replace_n(executor, instance, from, to, *count),
+        ("to_upper", Value::String(instance), []) => to_upper(executor, instance),
+        // Like rust, negative numbers are shown using their two's complement representation
+        ("to_binary", Value::Integer(instance), []) => Ok(format!("{instance:b}").into()),
+        ("to_hex", Value::Integer(instance), []) => Ok(format!("{instance:x}").into()),
+        ("count_ones", Value::Integer(instance), []) => Ok((instance.count_ones() as i32).into()),
+        ("leading_zeros", Value::Integer(instance), []) => {
+            Ok((instance.leading_zeros() as i32).into())
+        }
+        ("repeat", Value::String(instance), [Value::Integer(count)]) => {
+            repeat(executor, instance, *count)
+        }
+        ("wrap", Value::String(instance), [Value::Integer(width)]) => {
+            wrap(executor, instance, *width)
+        }
+        ("join", Value::Array(instance, _, _), [Value::String(separator)]) => {
+            join(executor, instance.as_ref(), separator)
+        }
+        ("to_lower", Value::String(instance), []) => to_lower(executor, instance),
+        ("line_count", Value::String(instance), []) => line_count(executor, instance),
+        ("word_count", Value::String(instance), []) => word_count(executor, instance),
+        // Functions passed to these can execute arbitrary code (e.g. exit), so they return the
+        // result of the evaluation directly rather than only an ExecutionError.
+        ("map", Value::Array(instance, _, _), [Value::Function(function, _, return_type)]) => {
+            return array_map(stack, executor, instance.as_ref(), function, return_type)
+        }
+        ("filter", Value::Array(instance, array_type, _), [Value::Function(function, _, _)]) => {
+            return array_filter(stack, executor, instance.as_ref(), array_type, function)
+        }
+        ("positions_where", Value::Array(instance, _, _), [Value::Function(function, _, _)]) => {
+            return array_positions_where(stack, executor, instance.as_ref(), function)
+        }
+        ("each", Value::Array(instance, _, _), [Value::Function(function, _, _)]) => {
+            return array_each(stack, executor, instance.as_ref(), function)
+        }
+        (
+            "sort_by",
+            Value::Array(instance, array_type, mutable),
+            [Value::Function(function, _, _)],
+        ) => {
+            return array_sort_by(
+                stack,
+                executor,
+                instance.as_ref(),
+                array_type,
+                *mutable,
+                function,
+            )
+        }
+        ("partition", Value::Array(instance, array_type, _), [Value::Function(function, _, _)]) => {
+            return array_partition(stack, executor, instance.as_ref(), array_type, function)
+        }
+        ("reduce", Value::Array(instance, _, _), [Value::Function(function, _, _), initial]) => {
+            return array_reduce(stack, executor, instance.as_ref(), function, initial)
+        }
         (name, instance, args) => {
             let args = args
                 .iter()
@@ -96,7 +603,9 @@ pub fn call_builtin_instance<E: Executor>(
                 format!("No function found with name: {name} on type {instance_type} that accepts arguments {args}").into(),
             );
         }
-    }
+    };
+
+    Ok(result?)
 }
 
 fn parse_int<E: Executor>(_executor: &mut E, value: &str) -> Result<Value, ExecutionError> {
@@ -107,12 +616,69 @@ fn parse_int<E: Executor>(_executor: &mut E, value: &str) -> Result<Value, Execu
         .into())
 }
 
+/// Only finite values can be parsed, as nash has no way to represent infinity or NaN.
+fn parse_float<E: Executor>(_executor: &mut E, value: &str) -> Result<Value, ExecutionError> {
+    let parsed = f64::from_str(value).map_err::<ExecutionError, _>(|_| {
+        format!("Could not parse string {:} into float", value).into()
+    })?;
+    let float = Float::new(parsed).ok_or::<ExecutionError>(
+        format!(
+            "Could not parse string {:} into float because it is not finite",
+            value
+        )
+        .into(),
+    )?;
+
+    Ok(float.into())
+}
+
+/// Only valid unicode scalar values can be chars, so surrogates and anything above `0x10FFFF` are
+/// rejected.
+fn char_from_int<E: Executor>(_executor: &mut E, value: i32) -> Result<Value, ExecutionError> {
+    let Some(char) = u32::try_from(value).ok().and_then(char::from_u32) else {
+        return Err(format!("{value} is not a valid unicode code point").into());
+    };
+
+    Ok(char.into())
+}
+
+/// Single letter suffixes are powers of 1024 like most command line tools use, the explicit
+/// `Ki`/`Mi` style suffixes are the same, and `kB`/`MB` style suffixes are powers of 1000.
+fn parse_size<E: Executor>(_executor: &mut E, value: &str) -> Result<Value, ExecutionError> {
+    let split = value
+        .find(|char: char| !char.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, suffix) = value.split_at(split);
+
+    let multiplier: i64 = match suffix {
+        "" => 1,
+        "k" | "K" | "ki" | "Ki" => 1 << 10,
+        "M" | "Mi" => 1 << 20,
+        "G" | "Gi" => 1 << 30,
+        "T" | "Ti" => 1 << 40,
+        "kB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        _ => return Err(format!("Unknown size suffix {suffix} in {value}").into()),
+    };
+
+    let number = number
+        .parse::<i64>()
+        .map_err::<ExecutionError, _>(|_| format!("Could not parse size {value}").into())?;
+
+    let size = number
+        .checked_mul(multiplier)
+        .and_then(|size| i32::try_from(size).ok())
+        .ok_or::<ExecutionError>(
+            format!("Size {value} is too large to fit in an integer").into(),
+        )?;
+
+    Ok(size.into())
+}
+
 fn read<E: Executor>(executor: &mut E) -> Result<Value, ExecutionError> {
-    let mut buf = Vec::new();
-    executor
-        .stdin()
-        .read_until(b'\n', &mut buf)
-        .map_err::<ExecutionError, _>(|err| format!("Error reading from stdin: {err}").into())?;
+    let mut buf = read_line(executor)?;
 
     // Tidy up any newline stuff that is potentially here
     buf.pop();
@@ -120,11 +686,195 @@ fn read<E: Executor>(executor: &mut E) -> Result<Value, ExecutionError> {
         buf.pop();
     }
 
+    line_to_string(buf)
+}
+
+/// Same as `read`, but keeps the line terminator (if there is one) as part of the result.
+fn read_raw<E: Executor>(executor: &mut E) -> Result<Value, ExecutionError> {
+    line_to_string(read_line(executor)?)
+}
+
+fn read_line<E: Executor>(executor: &mut E) -> Result<Vec<u8>, ExecutionError> {
+    let mut buf = Vec::new();
+    executor
+        .stdin()
+        .read_until(b'\n', &mut buf)
+        .map_err::<ExecutionError, _>(|err| format!("Error reading from stdin: {err}").into())?;
+
+    Ok(buf)
+}
+
+fn line_to_string(buf: Vec<u8>) -> Result<Value, ExecutionError> {
     let value = String::from_utf8(buf).map_err::<ExecutionError, _>(|err| {
         format!("Bytes read from stdin was not valid utf8: {err}").into()
     })?;
 
-    return Ok(value.into());
+    Ok(value.into())
+}
+
+fn read_all<E: Executor>(executor: &mut E) -> Result<Value, ExecutionError> {
+    let mut value = String::new();
+    executor
+        .stdin()
+        .read_to_string(&mut value)
+        .map_err::<ExecutionError, _>(|err| format!("Error reading from stdin: {err}").into())?;
+
+    // Only the final newline is removed to match read, any others are part of the input
+    if value.ends_with('\n') {
+        value.pop();
+        if value.ends_with('\r') {
+            value.pop();
+        }
+    }
+
+    Ok(value.into())
+}
+
+fn url_encode<E: Executor>(_executor: &mut E, value: &str) -> Result<Value, ExecutionError> {
+    // Percent encode everything apart from the unreserved characters defined in RFC 3986
+    let mut result = String::new();
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            result.push(byte as char);
+        } else {
+            result += &format!("%{byte:02X}");
+        }
+    }
+
+    Ok(result.into())
+}
+
+fn url_decode<E: Executor>(_executor: &mut E, value: &str) -> Result<Value, ExecutionError> {
+    let mut bytes = Vec::new();
+    let mut iter = value.bytes();
+    while let Some(byte) = iter.next() {
+        if byte != b'%' {
+            bytes.push(byte);
+            continue;
+        }
+
+        let encoded = iter.next().zip(iter.next()).and_then(|(high, low)| {
+            let high = (high as char).to_digit(16)?;
+            let low = (low as char).to_digit(16)?;
+            Some((high * 16 + low) as u8)
+        });
+
+        let Some(encoded) = encoded else {
+            return Err(format!("Invalid percent encoding in string {value}").into());
+        };
+        bytes.push(encoded);
+    }
+
+    let result = String::from_utf8(bytes).map_err::<ExecutionError, _>(|err| {
+        format!("Percent decoded string was not valid utf8: {err}").into()
+    })?;
+
+    Ok(result.into())
+}
+
+fn abs<E: Executor>(_executor: &mut E, value: i32) -> Result<Value, ExecutionError> {
+    let result = value.checked_abs().ok_or::<ExecutionError>(
+        format!("Unable to get absolute value of {value} because it would overflow").into(),
+    )?;
+
+    Ok(result.into())
+}
+
+fn pow<E: Executor>(_executor: &mut E, base: i32, exponent: i32) -> Result<Value, ExecutionError> {
+    let exponent = u32::try_from(exponent).map_err::<ExecutionError, _>(|_| {
+        format!("Exponent must not be negative, but got {exponent}").into()
+    })?;
+    let result = base.checked_pow(exponent).ok_or::<ExecutionError>(
+        format!("Unable to raise {base} to the power of {exponent} because it would overflow")
+            .into(),
+    )?;
+
+    Ok(result.into())
+}
+
+fn pow_float<E: Executor>(
+    _executor: &mut E,
+    base: Float,
+    exponent: Float,
+) -> Result<Value, ExecutionError> {
+    let result = Float::new(base.value().powf(exponent.value())).ok_or::<ExecutionError>(
+        format!(
+            "Unable to raise {base} to the power of {exponent} because the result is not finite"
+        )
+        .into(),
+    )?;
+
+    Ok(result.into())
+}
+
+fn compare<E: Executor>(
+    _executor: &mut E,
+    left: &Value,
+    right: &Value,
+) -> Result<Value, ExecutionError> {
+    Ok(Value::Integer(match compare_values(left, right)? {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    }))
+}
+
+fn compare_values(left: &Value, right: &Value) -> Result<Ordering, ExecutionError> {
+    Ok(match (left, right) {
+        (Value::Integer(left), Value::Integer(right)) => left.cmp(right),
+        (Value::String(left), Value::String(right)) => left.cmp(right),
+        (Value::Boolean(left), Value::Boolean(right)) => left.cmp(right),
+        (left, right) => {
+            return Err(format!(
+                "Can not compare a value of type {} with a value of type {}",
+                left.get_type(),
+                right.get_type()
+            )
+            .into())
+        }
+    })
+}
+
+/// Checks the runtime type of a value, which can be more specific than its static type for values
+/// such as json. Mutability is part of the type, so `mut [integer]` won't match `[integer]`.
+fn assert_type<E: Executor>(
+    _context: &mut E,
+    value: &Value,
+    type_name: &str,
+) -> Result<Value, ExecutionError> {
+    let Some(expected_type) = TypeDefinition::parse_name(type_name) else {
+        return Err(format!("{type_name} is not a valid type").into());
+    };
+
+    let actual_type = value.get_type();
+    if actual_type != expected_type {
+        return Err(format!(
+            "Expected value of type {expected_type} but got value of type {actual_type}"
+        )
+        .into());
+    }
+
+    Ok(value.clone())
+}
+
+fn assert<E: Executor>(_context: &mut E, condition: bool) -> Result<Value, ExecutionError> {
+    if !condition {
+        return Err("Assertion failed".into());
+    }
+
+    Ok(Value::Void)
+}
+
+fn assert_eq<E: Executor>(
+    _context: &mut E,
+    left: &Value,
+    right: &Value,
+) -> Result<Value, ExecutionError> {
+    if Operator::Equal.execute(left.clone(), right.clone())? != Value::Boolean(true) {
+        return Err(format!("Assertion failed, expected {left} to equal {right}").into());
+    }
+
+    Ok(Value::Void)
 }
 
 fn open<E: Executor>(_context: &mut E, value: &str) -> Result<Value, ExecutionError> {
@@ -139,6 +889,10 @@ fn append<E: Executor>(_context: &mut E, value: &str) -> Result<Value, Execution
     Ok(Value::FileHandle(value.to_owned(), FileMode::Append))
 }
 
+fn tee<E: Executor>(_context: &mut E, value: &str) -> Result<Value, ExecutionError> {
+    Ok(Value::FileHandle(value.to_owned(), FileMode::Tee))
+}
+
 fn out<E: Executor>(executor: &mut E, value: &str) -> Result<Value, ExecutionError> {
     if let Err(err) = writeln!(executor.stdout(), "{:}", value) {
         return Err(format!("Error writing to stdout: {err}").into());
@@ -155,26 +909,216 @@ fn err<E: Executor>(executor: &mut E, value: &str) -> Result<Value, ExecutionErr
     return Ok(Value::Void);
 }
 
+/// Falls back to another value when a value is void, which is the case for void functions and
+/// json nulls.
+fn default<E: Executor>(
+    _executor: &mut E,
+    value: &Value,
+    fallback: &Value,
+) -> Result<Value, ExecutionError> {
+    if matches!(value, Value::Void | Value::Null) {
+        return Ok(fallback.clone());
+    }
+
+    Ok(value.clone())
+}
+
+/// Returns the first argument that isn't null, or null if they all are.
+fn coalesce<E: Executor>(_executor: &mut E, values: &[Value]) -> Result<Value, ExecutionError> {
+    Ok(values
+        .iter()
+        .find(|value| **value != Value::Null)
+        .cloned()
+        .unwrap_or(Value::Null))
+}
+
 fn fmt<E: Executor>(_: &mut E, value: &Value) -> Result<Value, ExecutionError> {
     return Ok(format!("{value:}").into());
 }
 
-fn push<E: Executor>(
-    _context: &mut E,
-    array: &RefCell<Vec<Value>>,
-    value: &Value,
-) -> Result<Value, ExecutionError> {
-    array
-        .try_borrow_mut()
-        .map_err::<ExecutionError, _>(|_| {
-            format!("Cannot mutate array that is already being used").into()
-        })?
-        .push(value.clone());
-    Ok(Value::Void)
+fn str<E: Executor>(_: &mut E, value: &Value) -> Result<Value, ExecutionError> {
+    Ok(format_unquoted(value).into())
 }
 
-fn pop<E: Executor>(
-    _context: &mut E,
+/// Unlike `fmt` strings are left as is rather than being quoted, so that the result can be used
+/// when building output. Collections are still formatted the same way as `fmt`.
+pub fn format_unquoted(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.to_owned(),
+        Value::Char(value) => value.to_string(),
+        value => format!("{value}"),
+    }
+}
+
+/// Formats a value using a spec in the form `[<|>][0][width][.precision][w]`, e.g. `05` pads an
+/// integer with zeros up to a width of 5, `<10` pads a value with spaces on the right, and `.2`
+/// rounds a float to 2 decimal places. A trailing `w` measures the width in terminal columns
+/// rather than chars, see `display_width`.
+fn fmt_with_spec<E: Executor>(
+    _: &mut E,
+    value: &Value,
+    spec: &str,
+) -> Result<Value, ExecutionError> {
+    let invalid_spec = || -> ExecutionError { format!("Invalid format spec \"{spec}\"").into() };
+    let parse_number = |value: &str| -> Result<usize, ExecutionError> {
+        if value.is_empty() || !value.chars().all(|char| char.is_ascii_digit()) {
+            return Err(invalid_spec());
+        }
+        value.parse::<usize>().map_err(|_| invalid_spec())
+    };
+
+    let (align, rest) = match spec.chars().next() {
+        Some(align @ ('<' | '>')) => (Some(align), &spec[1..]),
+        _ => (None, spec),
+    };
+    let (by_display_width, rest) = match rest.strip_suffix('w') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    let (zero_pad, rest) = match rest.strip_prefix('0') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    let (width, precision) = match rest.split_once('.') {
+        Some((width, precision)) => (width, Some(parse_number(precision)?)),
+        None => (rest, None),
+    };
+    let width = if width.is_empty() {
+        0
+    } else {
+        parse_number(width)?
+    };
+
+    let text = match (value, precision) {
+        (Value::Float(value), Some(precision)) => format!("{:.precision$}", value.value()),
+        (_, Some(_)) => {
+            return Err("Precision can only be used when formatting floats".into());
+        }
+        (value, None) => value.to_string(),
+    };
+
+    let formatted = match value {
+        Value::Integer(value) if zero_pad => format!("{value:0width$}"),
+        _ if zero_pad => {
+            return Err("Zero padding can only be used when formatting integers".into());
+        }
+        // Like rust, numbers are right aligned by default and everything else is left aligned
+        Value::Integer(_) if align.is_none() => pad(&text, width, true, by_display_width),
+        _ if align == Some('>') => pad(&text, width, true, by_display_width),
+        _ => pad(&text, width, false, by_display_width),
+    };
+
+    Ok(formatted.into())
+}
+
+/// Pads based on the number of chars, or optionally the display width so that colored and wide
+/// characters still line up when printed in columns.
+fn pad(value: &str, width: usize, right_align: bool, by_display_width: bool) -> String {
+    let length = if by_display_width {
+        display_width(value)
+    } else {
+        value.chars().count()
+    };
+    let padding = " ".repeat(width.saturating_sub(length));
+    if right_align {
+        return padding + value;
+    }
+
+    value.to_owned() + &padding
+}
+
+/// The number of columns a string takes up when printed to a terminal, ANSI escape sequences
+/// (such as colors) take up no space and wide characters take up two columns.
+fn display_width(value: &str) -> usize {
+    let mut printed = String::new();
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        if char != '\x1b' {
+            printed.push(char);
+            continue;
+        }
+
+        // Control sequences like `ESC[31m` end with a char in the range @ to ~, other escapes
+        // are only a single char long
+        if let Some('[') = chars.next() {
+            chars.find(|char| ('@'..='~').contains(char));
+        }
+    }
+
+    printed.width()
+}
+
+/// Runs the pipeline with the executor, keeping track of how long it took for profiling.
+pub fn run_pipeline<E: Executor>(
+    executor: &mut E,
+    pipeline: &Pipeline,
+) -> Result<PipelineOutput, ExecutionError> {
+    let start = Instant::now();
+    let result = executor.run_pipeline(pipeline);
+    executor.profile().record_pipeline(start.elapsed());
+
+    result.map_err::<ExecutionError, _>(|err| format!("Error running command: {:}", err).into())
+}
+
+/// Error for a command that had to be killed because it ran past the command timeout.
+pub fn timeout_error<E: Executor>(executor: &E) -> String {
+    let timeout = executor.options().command_timeout.unwrap_or_default();
+    format!("Command was killed after exceeding the timeout of {timeout:?}")
+}
+
+/// Shorthand for `exec input => command`, runs the command with the input on stdin and returns
+/// whatever it wrote to stdout.
+fn run_with_input<E: Executor>(
+    executor: &mut E,
+    program: &str,
+    arguments: &[String],
+    input: &str,
+) -> Result<Value, ExecutionError> {
+    let pipeline = Pipeline::new(
+        vec![CommandDefinition::new(
+            program.to_owned(),
+            arguments.to_vec(),
+            false,
+        )],
+        Some(PipelineSource::Literal(input.to_owned() + "\n")),
+        None,
+    );
+
+    let result = run_pipeline(executor, &pipeline)?;
+
+    for command_output in result.command_outputs {
+        if command_output.timed_out {
+            return Err(timeout_error(executor).into());
+        }
+
+        if command_output.exit_code != 0 {
+            return Err(format!(
+                "Command returned non-zero exit code: ({})",
+                command_output.exit_code
+            )
+            .into());
+        }
+    }
+
+    Ok(result.stdout.unwrap_or_default().into())
+}
+
+fn push<E: Executor>(
+    _context: &mut E,
+    array: &RefCell<Vec<Value>>,
+    value: &Value,
+) -> Result<Value, ExecutionError> {
+    array
+        .try_borrow_mut()
+        .map_err::<ExecutionError, _>(|_| {
+            format!("Cannot mutate array that is already being used").into()
+        })?
+        .push(value.clone());
+    Ok(Value::Void)
+}
+
+fn pop<E: Executor>(
+    _context: &mut E,
     array: &RefCell<Vec<Value>>,
 ) -> Result<Value, ExecutionError> {
     Ok(array
@@ -186,6 +1130,61 @@ fn pop<E: Executor>(
         .ok_or::<ExecutionError>("Unable to pop array with no elements".into())?)
 }
 
+fn array_insert<E: Executor>(
+    _context: &mut E,
+    array: &RefCell<Vec<Value>>,
+    index: i32,
+    value: &Value,
+) -> Result<Value, ExecutionError> {
+    let mut array = array.try_borrow_mut().map_err::<ExecutionError, _>(|_| {
+        "Cannot mutate array that is already being used".into()
+    })?;
+
+    // Inserting at the length is allowed, it is the same as pushing to the end
+    let len = array.len();
+    let index = usize::try_from(index)
+        .ok()
+        .filter(|index| *index <= len)
+        .ok_or::<ExecutionError>(
+            format!("Cannot insert at index {index} because array only has {len} elements").into(),
+        )?;
+
+    array.insert(index, value.clone());
+    Ok(Value::Void)
+}
+
+fn array_remove<E: Executor>(
+    _context: &mut E,
+    array: &RefCell<Vec<Value>>,
+    index: i32,
+) -> Result<Value, ExecutionError> {
+    let mut array = array.try_borrow_mut().map_err::<ExecutionError, _>(|_| {
+        "Cannot mutate array that is already being used".into()
+    })?;
+
+    let len = array.len();
+    let index = usize::try_from(index)
+        .ok()
+        .filter(|index| *index < len)
+        .ok_or::<ExecutionError>(
+            format!("Cannot remove element at index {index} because array only has {len} elements")
+                .into(),
+        )?;
+
+    Ok(array.remove(index))
+}
+
+fn array_clear<E: Executor>(
+    _context: &mut E,
+    array: &RefCell<Vec<Value>>,
+) -> Result<Value, ExecutionError> {
+    array
+        .try_borrow_mut()
+        .map_err::<ExecutionError, _>(|_| "Cannot mutate array that is already being used".into())?
+        .clear();
+    Ok(Value::Void)
+}
+
 fn array_len<E: Executor>(
     _context: &mut E,
     array: &RefCell<Vec<Value>>,
@@ -201,6 +1200,251 @@ fn array_len<E: Executor>(
     ))
 }
 
+fn is_empty<E: Executor>(_context: &mut E, instance: &Value) -> Result<Value, ExecutionError> {
+    let is_empty = match instance {
+        Value::String(value) => value.is_empty(),
+        Value::Array(values, _, _) => values.borrow().is_empty(),
+        Value::Tuple(values) => values.is_empty(),
+        _ => return Err(format!("Unable to check if {instance} is empty").into()),
+    };
+
+    Ok(is_empty.into())
+}
+
+fn array_windows<E: Executor>(
+    _context: &mut E,
+    array: &RefCell<Vec<Value>>,
+    array_type: &Type,
+    size: i32,
+) -> Result<Value, ExecutionError> {
+    let size = usize::try_from(size)
+        .ok()
+        .filter(|size| *size > 0)
+        .ok_or::<ExecutionError>(format!("Window size must be positive, but got {size}").into())?;
+
+    let windows = array
+        .borrow()
+        .windows(size)
+        .map(|window| Value::new_array(window.to_vec(), array_type.clone(), false))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Value::new_array(
+        windows,
+        Type::Array(Box::new(array_type.clone()), false),
+        false,
+    )
+}
+
+fn array_index_of<E: Executor>(
+    _context: &mut E,
+    array: &RefCell<Vec<Value>>,
+    value: &Value,
+) -> Result<Value, ExecutionError> {
+    let Some(index) = array.borrow().iter().position(|item| item == value) else {
+        return Ok(Value::Integer(-1));
+    };
+
+    array_index(index)
+}
+
+/// Like `index_of` but returns the index of every matching item instead of only the first.
+fn array_positions<E: Executor>(
+    _context: &mut E,
+    array: &RefCell<Vec<Value>>,
+    value: &Value,
+) -> Result<Value, ExecutionError> {
+    let mut positions = Vec::new();
+    for (index, item) in array.borrow().iter().enumerate() {
+        if item == value {
+            positions.push(array_index(index)?);
+        }
+    }
+
+    Value::new_array(positions, Type::Integer, false)
+}
+
+/// Converts a position in an array into an integer value, as used by `index_of` and indexed for
+/// loops.
+pub fn array_index(index: usize) -> Result<Value, ExecutionError> {
+    Ok(Value::Integer(
+        index.try_into().map_err::<ExecutionError, _>(|err| {
+            format!("Unable to convert array index into i32: {err}").into()
+        })?,
+    ))
+}
+
+/// Returns a new immutable array with the items in reverse order, the original array is left
+/// unchanged even if it is mutable.
+fn array_reverse<E: Executor>(
+    _context: &mut E,
+    array: &RefCell<Vec<Value>>,
+    array_type: &Type,
+) -> Result<Value, ExecutionError> {
+    let values = array.borrow().iter().rev().cloned().collect::<Vec<_>>();
+    Value::new_array(values, array_type.clone(), false)
+}
+
+fn integer_items(array: &RefCell<Vec<Value>>) -> Result<Vec<i32>, ExecutionError> {
+    array
+        .borrow()
+        .iter()
+        .map(|item| match item {
+            Value::Integer(item) => Ok(*item),
+            item => Err(format!("Expected an integer but got {item}").into()),
+        })
+        .collect()
+}
+
+fn array_sum<E: Executor>(
+    _context: &mut E,
+    array: &RefCell<Vec<Value>>,
+) -> Result<Value, ExecutionError> {
+    let mut total: i32 = 0;
+    for item in integer_items(array)? {
+        total = total
+            .checked_add(item)
+            .ok_or::<ExecutionError>("Unable to sum array because it would overflow".into())?;
+    }
+
+    Ok(total.into())
+}
+
+/// Used for `min` and `max`, which have no sensible result for an empty array so they error.
+fn array_min_max<E: Executor>(
+    _context: &mut E,
+    array: &RefCell<Vec<Value>>,
+    name: &str,
+    reduce: fn(i32, i32) -> i32,
+) -> Result<Value, ExecutionError> {
+    let Some(result) = integer_items(array)?.into_iter().reduce(reduce) else {
+        return Err(format!("Unable to get {name} of an empty array").into());
+    };
+
+    Ok(result.into())
+}
+
+fn slice_range(
+    start: i32,
+    end: i32,
+    len: usize,
+    (container, items): (&str, &str),
+) -> Result<std::ops::Range<usize>, ExecutionError> {
+    if start > end {
+        return Err(
+            format!("Cannot slice from {start} to {end} because start is after end").into(),
+        );
+    }
+
+    let (Ok(start), Ok(end)) = (usize::try_from(start), usize::try_from(end)) else {
+        return Err(format!("Cannot slice from {start} to {end} because it is negative").into());
+    };
+
+    if end > len {
+        return Err(format!(
+            "Cannot slice from {start} to {end} because {container} only has {len} {items}"
+        )
+        .into());
+    }
+
+    Ok(start..end)
+}
+
+fn array_slice<E: Executor>(
+    _context: &mut E,
+    array: &RefCell<Vec<Value>>,
+    array_type: &Type,
+    start: i32,
+    end: i32,
+) -> Result<Value, ExecutionError> {
+    let array = array.borrow();
+    let range = slice_range(start, end, array.len(), ("array", "elements"))?;
+    Value::new_array(array[range].to_vec(), array_type.clone(), false)
+}
+
+/// Slices by characters rather than bytes, so multibyte characters are never split.
+fn string_slice<E: Executor>(
+    _context: &mut E,
+    string: &str,
+    start: i32,
+    end: i32,
+) -> Result<Value, ExecutionError> {
+    let chars = string.chars().collect::<Vec<_>>();
+    let range = slice_range(start, end, chars.len(), ("string", "characters"))?;
+    Ok(chars[range].iter().collect::<String>().into())
+}
+
+/// Returns a sorted copy of the array, the original is left as it was so that sorting can't
+/// change other references to a mutable array.
+fn array_sort<E: Executor>(
+    _context: &mut E,
+    array: &RefCell<Vec<Value>>,
+    array_type: &Type,
+    mutable: bool,
+) -> Result<Value, ExecutionError> {
+    let mut values = array.borrow().clone();
+    let mut error = None;
+    values.sort_by(|left, right| {
+        compare_values(left, right).unwrap_or_else(|err| {
+            error.get_or_insert(err);
+            Ordering::Equal
+        })
+    });
+
+    if let Some(error) = error {
+        return Err(error);
+    }
+
+    Value::new_array(values, array_type.clone(), mutable)
+}
+
+fn tuple_to_array<E: Executor>(_context: &mut E, tuple: &[Value]) -> Result<Value, ExecutionError> {
+    let Some(item_type) = is_duplicates(tuple.iter().map(|value| value.get_type())) else {
+        return Err("Only tuples with items of the same type can be converted to an array".into());
+    };
+
+    Value::new_array(tuple.to_vec(), item_type, false)
+}
+
+fn array_to_tuple<E: Executor>(
+    _context: &mut E,
+    array: &RefCell<Vec<Value>>,
+    length: i32,
+) -> Result<Value, ExecutionError> {
+    let array = array.borrow();
+    if usize::try_from(length).ok() != Some(array.len()) {
+        return Err(format!(
+            "Cannot convert array with {} elements into a tuple with {length} items",
+            array.len()
+        )
+        .into());
+    }
+
+    Ok(Value::Tuple(array.clone()))
+}
+
+fn tuple_len<E: Executor>(_context: &mut E, tuple: &[Value]) -> Result<Value, ExecutionError> {
+    Ok(Value::Integer(
+        tuple.len().try_into().map_err::<ExecutionError, _>(|err| {
+            format!("Unable to convert tuple length into i32: {err}").into()
+        })?,
+    ))
+}
+
+fn string_display_width<E: Executor>(
+    _context: &mut E,
+    string: &str,
+) -> Result<Value, ExecutionError> {
+    Ok(Value::Integer(
+        display_width(string)
+            .try_into()
+            .map_err::<ExecutionError, _>(|err| {
+                format!("Unable to convert string width into i32: {err}").into()
+            })?,
+    ))
+}
+
+/// The length in bytes rather than characters, so it won't match the indexes used by `slice` for
+/// strings containing multibyte characters.
 fn string_len<E: Executor>(_context: &mut E, string: &str) -> Result<Value, ExecutionError> {
     Ok(Value::Integer(
         string
@@ -212,6 +1456,232 @@ fn string_len<E: Executor>(_context: &mut E, string: &str) -> Result<Value, Exec
     ))
 }
 
+fn array_map<E: Executor>(
+    stack: &mut Stack,
+    executor: &mut E,
+    array: &RefCell<Vec<Value>>,
+    function: &str,
+    return_type: &Type,
+) -> EvaluationResult<Value> {
+    // Clone the items so the function is free to mutate the array while we iterate
+    let items = array.borrow().clone();
+    let mut results = Vec::new();
+    for item in items {
+        results.push(stack.execute_function(function, None, vec![item], executor)?);
+    }
+
+    Ok(Value::new_array(results, return_type.clone(), false)?)
+}
+
+fn array_each<E: Executor>(
+    stack: &mut Stack,
+    executor: &mut E,
+    array: &RefCell<Vec<Value>>,
+    function: &str,
+) -> EvaluationResult<Value> {
+    let items = array.borrow().clone();
+    for item in items {
+        stack.execute_function(function, None, vec![item], executor)?;
+    }
+
+    Ok(Value::Void)
+}
+
+fn array_sort_by<E: Executor>(
+    stack: &mut Stack,
+    executor: &mut E,
+    array: &RefCell<Vec<Value>>,
+    array_type: &Type,
+    mutable: bool,
+    function: &str,
+) -> EvaluationResult<Value> {
+    let mut values = array.borrow().clone();
+    let mut error = None;
+    values.sort_by(|left, right| {
+        if error.is_some() {
+            return Ordering::Equal;
+        }
+
+        match stack.execute_function(function, None, vec![left.clone(), right.clone()], executor) {
+            Ok(Value::Integer(result)) => result.cmp(&0),
+            Ok(_) => {
+                error = Some("Function passed to sort_by must return an integer".into());
+                Ordering::Equal
+            }
+            Err(err) => {
+                error = Some(err);
+                Ordering::Equal
+            }
+        }
+    });
+
+    if let Some(error) = error {
+        return Err(error);
+    }
+
+    Ok(Value::new_array(values, array_type.clone(), mutable)?)
+}
+
+fn array_filter<E: Executor>(
+    stack: &mut Stack,
+    executor: &mut E,
+    array: &RefCell<Vec<Value>>,
+    array_type: &Type,
+    function: &str,
+) -> EvaluationResult<Value> {
+    let items = array.borrow().clone();
+    let mut results = Vec::new();
+    for item in items {
+        let Value::Boolean(keep) =
+            stack.execute_function(function, None, vec![item.clone()], executor)?
+        else {
+            return Err("Function passed to filter must return a boolean".into());
+        };
+
+        if keep {
+            results.push(item);
+        }
+    }
+
+    Ok(Value::new_array(results, array_type.clone(), false)?)
+}
+
+fn array_positions_where<E: Executor>(
+    stack: &mut Stack,
+    executor: &mut E,
+    array: &RefCell<Vec<Value>>,
+    function: &str,
+) -> EvaluationResult<Value> {
+    let items = array.borrow().clone();
+    let mut positions = Vec::new();
+    for (index, item) in items.into_iter().enumerate() {
+        let Value::Boolean(is_match) =
+            stack.execute_function(function, None, vec![item], executor)?
+        else {
+            return Err("Function passed to positions_where must return a boolean".into());
+        };
+
+        if is_match {
+            positions.push(array_index(index)?);
+        }
+    }
+
+    Ok(Value::new_array(positions, Type::Integer, false)?)
+}
+
+fn array_partition<E: Executor>(
+    stack: &mut Stack,
+    executor: &mut E,
+    array: &RefCell<Vec<Value>>,
+    array_type: &Type,
+    function: &str,
+) -> EvaluationResult<Value> {
+    let items = array.borrow().clone();
+    let mut matching = Vec::new();
+    let mut not_matching = Vec::new();
+    for item in items {
+        let Value::Boolean(is_match) =
+            stack.execute_function(function, None, vec![item.clone()], executor)?
+        else {
+            return Err("Function passed to partition must return a boolean".into());
+        };
+
+        if is_match {
+            matching.push(item);
+        } else {
+            not_matching.push(item);
+        }
+    }
+
+    Ok(Value::Tuple(vec![
+        Value::new_array(matching, array_type.clone(), false)?,
+        Value::new_array(not_matching, array_type.clone(), false)?,
+    ]))
+}
+
+fn array_reduce<E: Executor>(
+    stack: &mut Stack,
+    executor: &mut E,
+    array: &RefCell<Vec<Value>>,
+    function: &str,
+    initial: &Value,
+) -> EvaluationResult<Value> {
+    let items = array.borrow().clone();
+    let mut accumulator = initial.clone();
+    for item in items {
+        accumulator = stack.execute_function(function, None, vec![accumulator, item], executor)?;
+    }
+
+    Ok(accumulator)
+}
+
+fn map_len<E: Executor>(
+    _context: &mut E,
+    map: &RefCell<HashMap<String, Value>>,
+) -> Result<Value, ExecutionError> {
+    Ok(Value::Integer(
+        map.borrow()
+            .len()
+            .try_into()
+            .map_err::<ExecutionError, _>(|err| {
+                format!("Unable to convert map length into i32: {err}").into()
+            })?,
+    ))
+}
+
+fn map_get<E: Executor>(
+    _context: &mut E,
+    map: &RefCell<HashMap<String, Value>>,
+    key: &str,
+) -> Result<Value, ExecutionError> {
+    Ok(map
+        .borrow()
+        .get(key)
+        .ok_or::<ExecutionError>(format!("Map does not contain key \"{key}\"").into())?
+        .clone())
+}
+
+fn map_set<E: Executor>(
+    _context: &mut E,
+    map: &RefCell<HashMap<String, Value>>,
+    key: &str,
+    value: &Value,
+) -> Result<Value, ExecutionError> {
+    map.try_borrow_mut()
+        .map_err::<ExecutionError, _>(|_| "Cannot mutate map that is already being used".into())?
+        .insert(key.to_owned(), value.clone());
+    Ok(Value::Void)
+}
+
+fn map_has<E: Executor>(
+    _context: &mut E,
+    map: &RefCell<HashMap<String, Value>>,
+    key: &str,
+) -> Result<Value, ExecutionError> {
+    Ok(map.borrow().contains_key(key).into())
+}
+
+fn map_keys<E: Executor>(
+    _context: &mut E,
+    map: &RefCell<HashMap<String, Value>>,
+) -> Result<Value, ExecutionError> {
+    // Keys are sorted so that iterating over them is deterministic
+    let mut keys = map.borrow().keys().cloned().collect::<Vec<_>>();
+    keys.sort();
+    Value::new_array(keys, Type::String, false)
+}
+
+fn map_remove<E: Executor>(
+    _context: &mut E,
+    map: &RefCell<HashMap<String, Value>>,
+    key: &str,
+) -> Result<Value, ExecutionError> {
+    map.try_borrow_mut()
+        .map_err::<ExecutionError, _>(|_| "Cannot mutate map that is already being used".into())?
+        .remove(key)
+        .ok_or::<ExecutionError>(format!("Map does not contain key \"{key}\"").into())
+}
+
 fn ends_with<E: Executor>(
     _context: &mut E,
     instance: &str,
@@ -220,24 +1690,411 @@ fn ends_with<E: Executor>(
     Ok(instance.ends_with(value).into())
 }
 
-fn glob<E: Executor>(_context: &mut E, pattern: &str) -> Result<Value, ExecutionError> {
-    let paths = glob::glob(pattern)
-        .map_err::<ExecutionError, _>(|err| {
-            format!("Invalid pattern provided to glob: {err}").into()
-        })?
-        .map(|path| {
-            let path = path
+fn starts_with<E: Executor>(
+    _context: &mut E,
+    instance: &str,
+    value: &str,
+) -> Result<Value, ExecutionError> {
+    Ok(instance.starts_with(value).into())
+}
+
+fn string_contains<E: Executor>(
+    _context: &mut E,
+    instance: &str,
+    value: &str,
+) -> Result<Value, ExecutionError> {
+    Ok(instance.contains(value).into())
+}
+
+// Returns the byte index of the first match (or -1 if there is no match), which is consistent
+// with len returning the length of a string in bytes, but means that for strings containing
+// multibyte characters the index will not be the same as the number of characters before it.
+fn string_index_of<E: Executor>(
+    _context: &mut E,
+    instance: &str,
+    value: &str,
+) -> Result<Value, ExecutionError> {
+    let Some(index) = instance.find(value) else {
+        return Ok(Value::Integer(-1));
+    };
+
+    Ok(Value::Integer(
+        index.try_into().map_err::<ExecutionError, _>(|err| {
+            format!("Unable to convert string index into i32: {err}").into()
+        })?,
+    ))
+}
+
+fn split<E: Executor>(
+    _context: &mut E,
+    instance: &str,
+    separator: &str,
+) -> Result<Value, ExecutionError> {
+    // Splitting on an empty string gives back each character, rather than the empty
+    // strings that str::split would add to the start and end.
+    if separator.is_empty() {
+        return chars(_context, instance);
+    }
+
+    let values = instance
+        .split(separator)
+        .map(|value| value.to_owned())
+        .collect::<Vec<_>>();
+
+    Value::new_array(values, Type::String, false)
+}
+
+/// Splits at a character position, using the same grapheme clusters as `chars` so multibyte
+/// characters are never split in half.
+fn split_at<E: Executor>(
+    _context: &mut E,
+    instance: &str,
+    index: i32,
+) -> Result<Value, ExecutionError> {
+    let len = instance.graphemes(true).count();
+    let Some(position) = usize::try_from(index).ok().filter(|index| *index <= len) else {
+        return Err(format!(
+            "Cannot split at index {index} because string only has {len} characters"
+        )
+        .into());
+    };
+
+    let byte_index = instance
+        .grapheme_indices(true)
+        .nth(position)
+        .map(|(byte_index, _)| byte_index)
+        .unwrap_or(instance.len());
+    let (left, right) = instance.split_at(byte_index);
+
+    Ok(Value::Tuple(vec![
+        left.to_owned().into(),
+        right.to_owned().into(),
+    ]))
+}
+
+/// Characters are grapheme clusters rather than chars, so that emoji and letters with combining
+/// marks are kept together as what a reader would see as a single character.
+fn chars<E: Executor>(_context: &mut E, instance: &str) -> Result<Value, ExecutionError> {
+    let values = instance
+        .graphemes(true)
+        .map(|value| value.to_owned())
+        .collect::<Vec<_>>();
+
+    Value::new_array(values, Type::String, false)
+}
+
+fn trim<E: Executor>(_context: &mut E, instance: &str) -> Result<Value, ExecutionError> {
+    Ok(instance.trim().to_owned().into())
+}
+
+fn number_lines<E: Executor>(_context: &mut E, instance: &str) -> Result<Value, ExecutionError> {
+    Ok(formatting::number_lines(instance).into())
+}
+
+fn replace<E: Executor>(
+    _context: &mut E,
+    instance: &str,
+    from: &str,
+    to: &str,
+) -> Result<Value, ExecutionError> {
+    Ok(instance.replace(from, to).into())
+}
+
+/// Replaces each `{name}` in the template with the matching value from the map, `{{` and `}}` can
+/// be used to write literal braces. Missing keys are an error unless `keep_missing` is set, in
+/// which case the placeholder is left as is.
+fn format_map<E: Executor>(
+    _context: &mut E,
+    template: &str,
+    values: &HashMap<String, Value>,
+    keep_missing: bool,
+) -> Result<Value, ExecutionError> {
+    let mut result = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '{' if chars.next_if_eq(&'{').is_some() => result.push('{'),
+            '}' if chars.next_if_eq(&'}').is_some() => result.push('}'),
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(next) => name.push(next),
+                        None => {
+                            return Err(format!(
+                                "Unterminated placeholder {{{name} in format string"
+                            )
+                            .into())
+                        }
+                    }
+                }
+
+                match values.get(&name) {
+                    Some(Value::String(value)) => result.push_str(value),
+                    Some(value) => result.push_str(&value.to_string()),
+                    None if keep_missing => result.push_str(&format!("{{{name}}}")),
+                    None => return Err(format!("No value found for placeholder {{{name}}}").into()),
+                }
+            }
+            '}' => return Err("Unmatched } in format string".into()),
+            char => result.push(char),
+        }
+    }
+
+    Ok(result.into())
+}
+
+fn replace_n<E: Executor>(
+    _context: &mut E,
+    instance: &str,
+    from: &str,
+    to: &str,
+    count: i32,
+) -> Result<Value, ExecutionError> {
+    if from.is_empty() {
+        return Err("Can not replace an empty string".into());
+    }
+    let count = usize::try_from(count).map_err::<ExecutionError, _>(|_| {
+        format!("Replacement count must not be negative, but got {count}").into()
+    })?;
+
+    Ok(instance.replacen(from, to, count).into())
+}
+
+fn to_upper<E: Executor>(_context: &mut E, instance: &str) -> Result<Value, ExecutionError> {
+    Ok(instance.to_uppercase().into())
+}
+
+fn repeat<E: Executor>(
+    _context: &mut E,
+    instance: &str,
+    count: i32,
+) -> Result<Value, ExecutionError> {
+    let count = usize::try_from(count).map_err::<ExecutionError, _>(|_| {
+        format!("Repeat count must not be negative, but got {count}").into()
+    })?;
+
+    Ok(instance.repeat(count).into())
+}
+
+/// Wraps each line at word boundaries so that it fits within the display width, existing line
+/// breaks are kept but runs of whitespace between words are collapsed into a single space. Words
+/// that are wider than the width are left intact on their own line.
+fn wrap<E: Executor>(
+    _context: &mut E,
+    instance: &str,
+    width: i32,
+) -> Result<Value, ExecutionError> {
+    let Some(width) = usize::try_from(width).ok().filter(|width| *width > 0) else {
+        return Err(format!("Wrap width must be positive, but got {width}").into());
+    };
+
+    let mut lines = Vec::new();
+    for line in instance.lines() {
+        let mut current = String::new();
+        for word in line.split_whitespace() {
+            if current.is_empty() {
+                current = word.to_owned();
+            } else if display_width(&current) + 1 + display_width(word) <= width {
+                current += " ";
+                current += word;
+            } else {
+                lines.push(current);
+                current = word.to_owned();
+            }
+        }
+        lines.push(current);
+    }
+
+    Ok(lines.join("\n").into())
+}
+
+fn join<E: Executor>(
+    _context: &mut E,
+    array: &RefCell<Vec<Value>>,
+    separator: &str,
+) -> Result<Value, ExecutionError> {
+    let values = array
+        .borrow()
+        .iter()
+        .map(|value| match value {
+            Value::String(value) => Ok(value.to_owned()),
+            value => Err(format!("Can not join value {value} that is not a string").into()),
+        })
+        .collect::<Result<Vec<_>, ExecutionError>>()?;
+
+    Ok(values.join(separator).into())
+}
+
+fn to_lower<E: Executor>(_context: &mut E, instance: &str) -> Result<Value, ExecutionError> {
+    Ok(instance.to_lowercase().into())
+}
+
+/// Counts lines the same way as `str::lines`, so a trailing newline doesn't add an extra empty line.
+fn line_count<E: Executor>(_context: &mut E, instance: &str) -> Result<Value, ExecutionError> {
+    Ok(Value::Integer(
+        instance
+            .lines()
+            .count()
+            .try_into()
+            .map_err::<ExecutionError, _>(|err| {
+                format!("Unable to convert line count into i32: {err}").into()
+            })?,
+    ))
+}
+
+fn word_count<E: Executor>(_context: &mut E, instance: &str) -> Result<Value, ExecutionError> {
+    Ok(Value::Integer(
+        instance
+            .split_whitespace()
+            .count()
+            .try_into()
+            .map_err::<ExecutionError, _>(|err| {
+                format!("Unable to convert word count into i32: {err}").into()
+            })?,
+    ))
+}
+
+fn env<E: Executor>(executor: &mut E, name: &str) -> Result<Value, ExecutionError> {
+    let value = executor
+        .get_env(name)
+        .ok_or::<ExecutionError>(format!("Environment variable {name} is not set").into())?;
+
+    Ok(value.into())
+}
+
+fn set_env<E: Executor>(
+    executor: &mut E,
+    name: &str,
+    value: &str,
+) -> Result<Value, ExecutionError> {
+    if name.is_empty() || name.contains(['=', '\0']) {
+        return Err(format!("Invalid environment variable name: \"{name}\"").into());
+    }
+    if value.contains('\0') {
+        return Err("Environment variable values can not contain null characters".into());
+    }
+
+    executor.set_env(name, value);
+
+    Ok(Value::Void)
+}
+
+fn read_file<E: Executor>(executor: &mut E, path: &str) -> Result<Value, ExecutionError> {
+    let contents = executor
+        .read_file(path)
+        .map_err::<ExecutionError, _>(|err| format!("Unable to read file {path}: {err}").into())?;
+
+    Ok(contents.into())
+}
+
+/// Lines are split the same way as `read` splits stdin, so a trailing `\r` is removed from each
+/// line and a final newline doesn't add an empty line at the end.
+fn read_lines<E: Executor>(executor: &mut E, path: &str) -> Result<Value, ExecutionError> {
+    let contents = executor
+        .read_file(path)
+        .map_err::<ExecutionError, _>(|err| format!("Unable to read file {path}: {err}").into())?;
+
+    let lines = contents.lines().map(|line| line.to_owned());
+    Value::new_array(lines, Type::String, false)
+}
+
+fn write_file<E: Executor>(
+    executor: &mut E,
+    path: &str,
+    contents: &str,
+) -> Result<Value, ExecutionError> {
+    executor
+        .write_file(path, contents)
+        .map_err::<ExecutionError, _>(|err| format!("Unable to write file {path}: {err}").into())?;
+
+    Ok(Value::Void)
+}
+
+fn script_args<E: Executor>(executor: &mut E) -> Result<Value, ExecutionError> {
+    Value::new_array(executor.args(), Type::String, false)
+}
+
+/// Like most shells, files starting with a `.` are only matched when `include_hidden` is set. If
+/// `relative` is set then any paths inside the current directory are made relative to it.
+fn glob<E: Executor>(
+    executor: &mut E,
+    pattern: &str,
+    include_hidden: bool,
+    relative: bool,
+) -> Result<Value, ExecutionError> {
+    let options = glob::MatchOptions {
+        require_literal_leading_dot: !include_hidden,
+        ..Default::default()
+    };
+    let current_dir = if relative {
+        Some(env::current_dir().map_err::<ExecutionError, _>(|err| {
+            format!("Unable to get current directory: {err}").into()
+        })?)
+    } else {
+        None
+    };
+
+    let mut paths = Vec::new();
+    let matches = glob::glob_with(pattern, options).map_err::<ExecutionError, _>(|err| {
+        format!("Invalid pattern provided to glob: {err}").into()
+    })?;
+    for path in matches {
+        let mut path = path.map_err::<ExecutionError, _>(|err| {
+            format!("Unable to get path while globing: {err}").into()
+        })?;
+        if let Some(current_dir) = &current_dir {
+            if let Ok(relative_path) = path.strip_prefix(current_dir) {
+                path = relative_path.to_path_buf();
+            }
+        }
+
+        match path.into_os_string().into_string() {
+            Ok(path) => paths.push(path),
+            Err(path) if executor.options().skip_invalid_glob_paths => {
+                writeln!(
+                    executor.stderr(),
+                    "Skipping path {} that is not in valid utf-8 encoding",
+                    path.to_string_lossy()
+                )
                 .map_err::<ExecutionError, _>(|err| {
-                    format!("Unable to get path while globing: {err}").into()
-                })?
-                .into_os_string()
-                .into_string()
-                .map_err::<ExecutionError, _>(|_| {
-                    format!("Path is not in valid utf-8 encoding").into()
+                    format!("Error writing to stderr: {err}").into()
                 })?;
-            return Ok::<String, ExecutionError>(path);
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+            }
+            Err(_) => return Err("Path is not in valid utf-8 encoding".into()),
+        }
+    }
 
     return Ok(Value::new_array(paths, Type::String, false)?);
 }
+
+/// Lists the entries of a directory in sorted order, if `full_paths` is set then each entry is
+/// joined onto the path of the directory rather than just being the entry name.
+fn list_dir<E: Executor>(
+    executor: &mut E,
+    path: &str,
+    full_paths: bool,
+) -> Result<Value, ExecutionError> {
+    let mut entries = executor
+        .list_dir(path)
+        .map_err::<ExecutionError, _>(|err| {
+            format!("Unable to list directory {path}: {err}").into()
+        })?;
+    entries.sort();
+
+    if full_paths {
+        entries = entries
+            .into_iter()
+            .map(|entry| {
+                Path::new(path)
+                    .join(entry)
+                    .into_os_string()
+                    .into_string()
+                    .map_err::<ExecutionError, _>(|_| "Path is not in valid utf-8 encoding".into())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+
+    Value::new_array(entries, Type::String, false)
+}
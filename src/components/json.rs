@@ -0,0 +1,131 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::ExecutionError;
+
+use super::values::{Float, Type, Value};
+
+/// Parses a json document into nested values, objects and arrays are given an item type of json
+/// as their contents can be of any type. Nulls become void values, and numbers become integers
+/// unless they have a fractional part or exponent. serde_json limits how deeply documents can be
+/// nested, so deeply nested input is an error rather than overflowing the stack.
+pub fn parse(value: &str) -> Result<Value, ExecutionError> {
+    let parsed = serde_json::from_str::<serde_json::Value>(value)
+        .map_err::<ExecutionError, _>(|err| format!("Unable to parse json: {err}").into())?;
+
+    convert(parsed)
+}
+
+pub fn serialize(value: &Value) -> Result<String, ExecutionError> {
+    let mut result = String::new();
+    serialize_into(value, &mut result)?;
+    Ok(result)
+}
+
+fn convert(value: serde_json::Value) -> Result<Value, ExecutionError> {
+    Ok(match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(value) => value.into(),
+        serde_json::Value::Number(number) => convert_number(&number)?,
+        serde_json::Value::String(value) => value.into(),
+        serde_json::Value::Array(values) => {
+            let values = values.into_iter().map(convert).collect::<Result<_, _>>()?;
+            Value::Array(Rc::new(RefCell::new(values)), Type::Json, false)
+        }
+        serde_json::Value::Object(entries) => {
+            let entries = entries
+                .into_iter()
+                .map(|(key, value)| Ok((key, convert(value)?)))
+                .collect::<Result<HashMap<_, _>, ExecutionError>>()?;
+            Value::Map(Rc::new(RefCell::new(entries)), Type::Json, false)
+        }
+    })
+}
+
+fn convert_number(number: &serde_json::Number) -> Result<Value, ExecutionError> {
+    if number.is_f64() {
+        return number
+            .as_f64()
+            .and_then(Float::new)
+            .map(Value::from)
+            .ok_or(format!("Json number {number} is not a valid float").into());
+    }
+
+    number
+        .as_i64()
+        .and_then(|value| i32::try_from(value).ok())
+        .map(Value::from)
+        .ok_or(format!("Json number {number} is out of range for an integer").into())
+}
+
+fn serialize_into(value: &Value, result: &mut String) -> Result<(), ExecutionError> {
+    match value {
+        Value::Void | Value::Null => result.push_str("null"),
+        Value::String(value) => serialize_string(value, result),
+        Value::Integer(value) => result.push_str(&value.to_string()),
+        Value::Float(value) => result.push_str(&value.to_string()),
+        Value::Char(value) => serialize_string(&value.to_string(), result),
+        Value::Boolean(value) => result.push_str(&value.to_string()),
+        Value::Array(values, _, _) => serialize_sequence(values.borrow().iter(), result)?,
+        Value::Tuple(values) => serialize_sequence(values.iter(), result)?,
+        Value::Map(entries, _, _) => {
+            // Sort the entries to match how maps are displayed, so the output is deterministic
+            let entries = entries.borrow();
+            let mut entries = entries.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(left, _)| *left);
+
+            result.push('{');
+            for (index, (key, value)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    result.push(',');
+                }
+                serialize_string(key, result);
+                result.push(':');
+                serialize_into(value, result)?;
+            }
+            result.push('}');
+        }
+        value => {
+            return Err(format!(
+                "Unable to convert value of type {} into json",
+                value.get_type()
+            )
+            .into())
+        }
+    }
+
+    Ok(())
+}
+
+fn serialize_sequence<'a, I: Iterator<Item = &'a Value>>(
+    values: I,
+    result: &mut String,
+) -> Result<(), ExecutionError> {
+    result.push('[');
+    for (index, value) in values.enumerate() {
+        if index > 0 {
+            result.push(',');
+        }
+        serialize_into(value, result)?;
+    }
+    result.push(']');
+
+    Ok(())
+}
+
+fn serialize_string(value: &str, result: &mut String) {
+    // Quotes are escaped the same way as when displaying a string, but json also requires
+    // backslashes and control characters to be escaped
+    result.push('"');
+    for char in value.chars() {
+        match char {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            char if char.is_control() => result.push_str(&format!("\\u{:04x}", char as u32)),
+            char => result.push(char),
+        }
+    }
+    result.push('"');
+}
@@ -1,21 +1,28 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    io::Write,
+    time::{Duration, Instant},
+};
 
-use root::Root;
+pub use root::*;
+pub use stack::CustomBuiltin;
 use stack::Stack;
-use values::{Type, Value};
+pub use values::{Type, Value};
 
 use crate::{
     errors::{self, ExecutionError, ParserError, PostProcessError},
     lexer::{Token, TokenValue},
     utils::iterators::Backtrackable,
-    Executor,
+    ExecutionProfile, Executor, NashError,
 };
 
 mod builtins;
+mod json;
 mod root;
 mod stack;
 mod values;
 
+/// A parsed script, which can be type checked and then executed.
 pub struct ComponentTree {
     root: Root,
 }
@@ -49,25 +56,168 @@ pub fn parse<'a, I: IntoIterator<Item = &'a Token<'a>>>(
 
 impl ComponentTree {
     pub fn post_process(&self) -> Result<(), PostProcessError> {
-        let mut context = PostProcessContext::new();
-        self.root.post_process(&mut context)?;
+        self.post_process_with_context(&mut PostProcessContext::new())
+    }
 
-        Ok(())
+    /// Type checks the whole tree, returning every error found instead of only the first.
+    pub fn post_process_all(&self) -> Vec<PostProcessError> {
+        self.root.post_process_all(&mut PostProcessContext::new())
     }
 
     pub fn execute<E: Executor>(
         &self,
         executor: &mut E,
     ) -> Result<ExecutionOutput, ExecutionError> {
-        let mut stack = Stack::new();
+        self.execute_with_stack(&mut Stack::new(), executor)
+    }
+
+    pub fn root(&self) -> &Root {
+        &self.root
+    }
+
+    pub fn into_root(self) -> Root {
+        self.root
+    }
+
+    fn post_process_with_context(
+        &self,
+        context: &mut PostProcessContext,
+    ) -> Result<(), PostProcessError> {
+        self.root.post_process(context)?;
+
+        Ok(())
+    }
+
+    fn execute_with_stack<E: Executor>(
+        &self,
+        stack: &mut Stack,
+        executor: &mut E,
+    ) -> Result<ExecutionOutput, ExecutionError> {
+        let start = Instant::now();
+        let profile_start = executor.profile().clone();
+        let result = self.root.execute(stack, executor);
+        if executor.options().profile {
+            Self::write_profile(executor, start.elapsed(), profile_start)?;
+        }
 
-        let exit_code = self.root.execute(&mut stack, executor).map_err(|mut err| {
-            err.set_call_stack(stack.get_call_stack().clone());
+        let exit_code = result.map_err(|mut err| {
+            // Errors raised inside a function already have the call stack from where they happened
+            if err.call_stack.is_none() {
+                err.set_call_stack(stack.get_call_stack().clone());
+            }
             return err;
         })?;
 
         return Ok(ExecutionOutput::new(exit_code));
     }
+
+    /// Splits the total time taken by the script into time spent waiting for commands and time
+    /// spent evaluating everything else, only counting pipelines run since `profile_start`.
+    fn write_profile<E: Executor>(
+        executor: &mut E,
+        total_time: Duration,
+        profile_start: ExecutionProfile,
+    ) -> Result<(), ExecutionError> {
+        let profile = executor.profile();
+        let pipeline_time = profile.pipeline_time - profile_start.pipeline_time;
+        let pipeline_count = profile.pipeline_count - profile_start.pipeline_count;
+        let interpreter_time = total_time.saturating_sub(pipeline_time);
+
+        if let Err(err) = writeln!(
+            executor.stderr(),
+            "Profile:\n  Total: {total_time:?}\n  Commands: {pipeline_time:?} ({pipeline_count} pipelines)\n  Interpreter: {interpreter_time:?}"
+        ) {
+            return Err(format!("Error writing to stderr: {err}").into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Keeps variables and functions declared by a script around after it has finished, so that
+/// following scripts executed in the same session can use them (e.g. for a REPL).
+pub struct Session {
+    stack: Stack,
+    context: PostProcessContext,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self {
+            stack: Stack::new(),
+            context: PostProcessContext::new(),
+        }
+    }
+
+    /// Forgets everything that has been declared in this session, registered builtins are kept.
+    pub fn reset(&mut self) {
+        self.stack.clear_user_scopes();
+        let custom_builtins = std::mem::take(&mut self.context.custom_builtins);
+        self.context = PostProcessContext::new();
+        self.context.custom_builtins = custom_builtins;
+    }
+
+    /// Makes a function from the embedding program available to scripts executed in this session,
+    /// it takes precedence over any builtin with the same name. Arguments are type checked before
+    /// the script runs, so the function will only be called with values of the given types.
+    pub fn register_builtin(
+        &mut self,
+        name: &str,
+        arguments: Vec<Type>,
+        return_type: Type,
+        function: CustomBuiltin,
+    ) {
+        self.context
+            .custom_builtins
+            .insert(name.to_owned(), (arguments, return_type.clone()));
+        self.stack.register_builtin(name, return_type, function);
+    }
+
+    pub fn execute<E: Executor>(
+        &mut self,
+        component_tree: &ComponentTree,
+        executor: &mut E,
+    ) -> Result<ExecutionOutput, NashError> {
+        // Type check against a copy and keep a copy of the stack's declarations, so a script that
+        // fails (while being type checked or run) leaves nothing behind in the session
+        let mut context = self.context.clone();
+        component_tree.post_process_with_context(&mut context)?;
+
+        let declarations = self.stack.declarations();
+        let result = component_tree.execute_with_stack(&mut self.stack, executor);
+        self.stack.clear_call_stack();
+        match result {
+            Ok(_) => self.context = context,
+            Err(_) => self.stack.restore_declarations(declarations),
+        }
+
+        Ok(result?)
+    }
+
+    /// Lists the signatures of every function declared in this session, sorted by name (e.g.
+    /// `func add(integer, integer): integer`).
+    pub fn functions(&self) -> Vec<String> {
+        let mut functions = self.context.functions.iter().collect::<Vec<_>>();
+        functions.sort_by_key(|(left, _)| *left);
+
+        functions
+            .into_iter()
+            .map(|(name, (arguments, return_type))| {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| argument.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("func {name}({arguments}): {return_type}")
+            })
+            .collect()
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 trait Tokens<'a> {
@@ -105,8 +255,9 @@ pub type EvaluationResult<T> = Result<T, EvaluationException>;
 pub enum ControlFlowOptions {
     Exit(u8),
     Return(Value),
-    Break(),
-    Continue(),
+    /// Loops only handle a break or continue if it has no label, or the label matches their own.
+    Break(Option<String>),
+    Continue(Option<String>),
 }
 
 pub enum EvaluationException {
@@ -126,9 +277,10 @@ impl<T: Into<ExecutionError>> From<T> for EvaluationException {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PostProcessContext {
     functions: HashMap<String, (Vec<Type>, Type)>,
+    custom_builtins: HashMap<String, (Vec<Type>, Type)>,
     scopes: Vec<Scope>,
 }
 
@@ -136,7 +288,8 @@ impl PostProcessContext {
     fn new() -> Self {
         Self {
             functions: HashMap::new(),
-            scopes: Vec::new(),
+            custom_builtins: HashMap::new(),
+            scopes: vec![Scope::new(ScopeType::Root)],
         }
     }
 
@@ -158,9 +311,15 @@ impl PostProcessContext {
         return None;
     }
 
-    fn has_parent_scope(&self, scope_type: &ScopeType) -> bool {
-        self.get_matching_parent_scope(|scope| scope_type == scope)
-            .is_some()
+    /// Checks there is a loop that a break or continue can apply to, if a label is given then
+    /// the loop must also have that label.
+    fn has_loop_scope(&self, label: Option<&str>) -> bool {
+        self.get_matching_parent_scope(|scope| match (scope, label) {
+            (ScopeType::Looped(_), None) => true,
+            (ScopeType::Looped(Some(loop_label)), Some(label)) => loop_label == label,
+            _ => false,
+        })
+        .is_some()
     }
 
     fn get_matching_parent_scope<F: FnMut(&ScopeType) -> bool>(
@@ -177,7 +336,7 @@ impl PostProcessContext {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Scope {
     variables: HashMap<String, Type>,
     scope_type: ScopeType,
@@ -197,6 +356,6 @@ enum ScopeType {
     Root,
     Block,
     Function(Type),
-    Looped,
+    Looped(Option<String>),
     Conditional,
 }
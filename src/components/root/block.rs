@@ -1,8 +1,11 @@
 use serde::Serialize;
 
 use crate::{
+    ast::Visitor,
     components::{
-        stack::Stack, values::Value, EvaluationResult, PostProcessContext, Scope, ScopeType,
+        stack::Stack,
+        values::{Type, Value},
+        EvaluationResult, PostProcessContext, Scope, ScopeType,
     },
     errors::PostProcessError,
     lexer::{Token, TokenValue},
@@ -10,11 +13,12 @@ use crate::{
     ExecutionError, Executor, ParserError,
 };
 
-use super::{statement::Statement, Tokens};
+use super::{expressions::Expression, statement::Statement, Tokens};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Block {
     pub statements: Vec<Statement>,
+    pub result: Option<Box<Expression>>,
 }
 
 impl Block {
@@ -27,16 +31,30 @@ impl Block {
             return Err("code block must start with {".into());
         };
 
+        let mut result = None;
         loop {
             if let Some(TokenValue::RightCurly()) = tokens.peek_value() {
                 tokens.next();
                 break;
             };
 
-            statements.push(Statement::parse(tokens)?);
+            let statement = Statement::parse_content(tokens)?;
+            match (statement, tokens.peek_value()) {
+                (statement, Some(TokenValue::Semicolon())) => {
+                    tokens.next();
+                    statements.push(statement);
+                }
+                // The last expression in a block can omit the ; to become the value of the block
+                (Statement::Expression(expression), Some(TokenValue::RightCurly())) => {
+                    tokens.next();
+                    result = Some(Box::new(expression));
+                    break;
+                }
+                _ => return Err("statement must end with ;".into()),
+            }
         }
 
-        return Ok(Block { statements });
+        Ok(Block { statements, result })
     }
 
     pub fn execute<E: Executor>(
@@ -57,34 +75,57 @@ impl Block {
         executor: &mut E,
     ) -> EvaluationResult<Value> {
         stack.push_scope();
-        initialize(stack)?;
-        for statement in &self.statements {
-            statement.execute(stack, executor)?;
-        }
+        let result = (|| {
+            initialize(stack)?;
+            for statement in &self.statements {
+                statement.execute(stack, executor)?;
+            }
+
+            if let Some(result) = &self.result {
+                return result.evaluate(stack, executor);
+            }
+
+            Ok(Value::Void)
+        })();
+
+        // Make sure the scope is popped even when the block exits early (e.g. from a break)
         stack.pop_scope();
-        return Ok(Value::Void);
+        result
     }
 
-    pub fn post_process(&self, context: &mut PostProcessContext) -> Result<(), PostProcessError> {
+    pub fn post_process(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
         self.post_process_with_initializer(|_| Ok(()), ScopeType::Block, context)
     }
 
-    pub fn post_process_with_initializer<
+    pub(super) fn post_process_with_initializer<
         F: FnOnce(&mut PostProcessContext) -> Result<(), PostProcessError>,
     >(
         &self,
         initialize: F,
         scope_type: ScopeType,
         context: &mut PostProcessContext,
-    ) -> Result<(), PostProcessError> {
+    ) -> Result<Type, PostProcessError> {
         // Add new variable scope for the block
         context.scopes.push(Scope::new(scope_type));
         initialize(context)?;
         for statement in &self.statements {
             statement.post_process(context)?;
         }
+        let result_type = match &self.result {
+            Some(result) => result.get_type(context)?,
+            None => Type::Void,
+        };
         context.scopes.pop();
 
-        Ok(())
+        Ok(result_type)
+    }
+
+    pub(crate) fn walk(&self, visitor: &mut dyn Visitor) {
+        for statement in &self.statements {
+            statement.walk(visitor);
+        }
+        if let Some(result) = &self.result {
+            result.walk(visitor);
+        }
     }
 }
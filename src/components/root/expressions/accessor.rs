@@ -1,6 +1,7 @@
 use serde::Serialize;
 
 use crate::{
+    ast::Visitor,
     components::{
         stack::Stack,
         values::{Type, Value},
@@ -69,11 +70,17 @@ impl DependentExpressionComponent for AccessorExpression {
         match &self.accessor {
             Accessor::Integer(index) => {
                 let Type::Tuple(tuple_types) = inner_type else {
-                    todo!("Error handling needed :)")
+                    return Err(
+                        format!("Cannot use get expression on value of type {inner_type}").into(),
+                    );
                 };
 
-                let Some(value) = tuple_types.get::<usize>((*index).try_into().unwrap()) else {
-                    todo!("Error handling needed :)")
+                let Some(value) = tuple_types.get(*index as usize) else {
+                    return Err(format!(
+                        "Cannot get element at index {index} because tuple only has {} elements",
+                        tuple_types.len()
+                    )
+                    .into());
                 };
 
                 Ok(value.clone())
@@ -81,6 +88,13 @@ impl DependentExpressionComponent for AccessorExpression {
             Accessor::Variable(variable) => Ok(variable.get_type_on_instance(inner_type, context)?),
         }
     }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        self.inner.walk(visitor);
+        if let Accessor::Variable(variable) = &self.accessor {
+            variable.walk(visitor);
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -93,10 +107,17 @@ impl Accessor {
     fn try_parse<'a, I: Iterator<Item = &'a Token<'a>>>(
         tokens: &mut Backtrackable<I>,
     ) -> Result<Option<Self>, ParserError> {
-        let Some(TokenValue::Dot()) = tokens.peek_value() else {
+        let checkpoint = tokens.checkpoint();
+        let Some(TokenValue::Dot()) = tokens.next_value() else {
+            tokens.backtrack(checkpoint);
             return Ok(None);
         };
-        tokens.next();
+
+        // Two dots is a range rather than an accessor
+        if let Some(TokenValue::Dot()) = tokens.peek_value() {
+            tokens.backtrack(checkpoint);
+            return Ok(None);
+        }
 
         if let Some(TokenValue::IntegerLiteral(integer)) = tokens.peek_value() {
             tokens.next();
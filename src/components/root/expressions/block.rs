@@ -1,6 +1,7 @@
 use serde::Serialize;
 
 use crate::{
+    ast::Visitor,
     components::{
         root::block::Block,
         stack::Stack,
@@ -42,9 +43,10 @@ impl ExpressionComponent for BlockExpression {
     }
 
     fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
-        // Make sure to post process the block itself
-        self.inner.post_process(context)?;
+        self.inner.post_process(context)
+    }
 
-        Ok(Type::Void)
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        self.inner.walk(visitor);
     }
 }
@@ -1,6 +1,7 @@
 use serde::Serialize;
 
 use crate::{
+    ast::Visitor,
     components::{
         stack::Stack,
         values::{Type, Value},
@@ -28,6 +29,12 @@ impl ExpressionComponent for BracketExpression {
         let checkpoint = tokens.checkpoint();
         tokens.next();
 
+        // Nothing between the brackets means this is an empty tuple
+        if let Some(TokenValue::RightBracket()) = tokens.peek_value() {
+            tokens.backtrack(checkpoint);
+            return Ok(None);
+        }
+
         let inner = Expression::parse(tokens)?;
 
         let Some(TokenValue::RightBracket()) = tokens.peek_value() else {
@@ -54,4 +61,8 @@ impl ExpressionComponent for BracketExpression {
     fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
         Ok(self.inner.get_type(context)?)
     }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        self.inner.walk(visitor);
+    }
 }
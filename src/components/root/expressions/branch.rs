@@ -1,6 +1,7 @@
 use serde::Serialize;
 
 use crate::{
+    ast::Visitor,
     components::{
         stack::Stack,
         values::{Type, Value},
@@ -9,6 +10,7 @@ use crate::{
     constants::{ELSE, IF},
     errors::PostProcessError,
     lexer::TokenValue,
+    utils::iterators::is_duplicates,
     Executor,
 };
 
@@ -79,22 +81,69 @@ impl ExpressionComponent for BranchExpression {
     }
 
     fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
+        self.get_branch_type(context, true)
+    }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        for (condition, block) in &self.conditional_blocks {
+            condition.walk(visitor);
+            block.walk(visitor);
+        }
+        if let Some(block) = &self.default_block {
+            block.walk(visitor);
+        }
+    }
+}
+
+impl BranchExpression {
+    /// Type checks an if whose value is thrown away (e.g. `if ready { a.pop() };`), which does
+    /// not need an else branch or for the branches to agree on a type.
+    pub(super) fn get_statement_type(
+        &self,
+        context: &mut PostProcessContext,
+    ) -> Result<Type, PostProcessError> {
+        self.get_branch_type(context, false)
+    }
+
+    fn get_branch_type(
+        &self,
+        context: &mut PostProcessContext,
+        used_as_value: bool,
+    ) -> Result<Type, PostProcessError> {
+        let mut types = Vec::new();
         for (condition, block) in &self.conditional_blocks {
             let Type::Boolean = condition.get_type(context)? else {
                 return Err("This expression must return a boolean".into());
             };
 
-            block.post_process_with_initializer(|_| Ok(()), ScopeType::Conditional, context)?;
+            types.push(block.post_process_with_initializer(
+                |_| Ok(()),
+                ScopeType::Conditional,
+                context,
+            )?);
         }
 
         if let Some(default_block) = &self.default_block {
-            default_block.post_process_with_initializer(
+            types.push(default_block.post_process_with_initializer(
                 |_| Ok(()),
                 ScopeType::Conditional,
                 context,
-            )?;
+            )?);
+        }
+
+        if !used_as_value {
+            return Ok(Type::Void);
+        }
+
+        let Some(result_type) = is_duplicates(types) else {
+            return Err("Expected all branches of an if to be the same type".into());
+        };
+
+        // Without an else the if might not run any branch, so there would be no value to use
+        if self.default_block.is_none() && result_type != Type::Void {
+            return Err("An if must have an else branch to be used as a value".into());
         }
 
-        Ok(Type::Void)
+        Ok(result_type)
     }
 }
@@ -1,6 +1,9 @@
+use std::cell::RefCell;
+
 use serde::Serialize;
 
 use crate::{
+    ast::Visitor,
     components::{
         stack::Stack,
         values::{Type, Value},
@@ -16,11 +19,15 @@ use crate::{
 use super::{Expression, ExpressionComponent};
 
 macro_rules! collection_expression_impl {
-    ($expression_type:ident, $start_token:expr, $end_token:expr) => {
+    ($expression_type:ident, $start_token:expr, $end_token:expr $(, $field:ident: $field_type:ty)*) => {
         #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
         pub struct $expression_type {
             values: Vec<Expression>,
             mutable: bool,
+            $(
+                #[serde(skip)]
+                $field: $field_type,
+            )*
         }
 
         impl $expression_type {
@@ -35,7 +42,11 @@ macro_rules! collection_expression_impl {
                 };
 
                 if let Some(values) = try_parse_collection(tokens, &$start_token, &$end_token)? {
-                    Ok(Some($expression_type { values, mutable }))
+                    Ok(Some($expression_type {
+                        values,
+                        mutable,
+                        $($field: Default::default(),)*
+                    }))
                 } else {
                     Ok(None)
                 }
@@ -125,12 +136,21 @@ impl ExpressionComponent for TupleExpression {
 
         return Ok(Type::Tuple(types));
     }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        for value in &self.values {
+            value.walk(visitor);
+        }
+    }
 }
 
 collection_expression_impl!(
     ArrayExpression,
     TokenValue::LeftSquare(),
-    TokenValue::RightSquare()
+    TokenValue::RightSquare(),
+    // The type checked element type, runtime values of a json array can have any json compatible
+    // type so it can't be worked out from the values themselves
+    element_type: RefCell<Option<Type>>
 );
 
 impl ExpressionComponent for ArrayExpression {
@@ -146,17 +166,20 @@ impl ExpressionComponent for ArrayExpression {
         executor: &mut E,
     ) -> EvaluationResult<Value> {
         let values = evaluate_collection(&self.values, stack, executor)?;
-        let mut array_types = values.iter().map(|x| x.get_type());
-        let Some(array_type) = array_types.next() else {
-            return Err("Unable to determine array type for empty array".into());
-        };
-        for item in array_types {
-            if item != array_type {
+        let array_type = if let Some(element_type) = self.element_type.borrow().clone() {
+            element_type
+        } else {
+            let mut array_types = values.iter().map(|x| x.get_type());
+            let Some(array_type) = array_types.next() else {
+                return Err("Unable to determine array type for empty array".into());
+            };
+            if array_types.any(|item| item != array_type) {
                 return Err("Array must have all values of the same type".into());
             }
-        }
+            array_type
+        };
 
-        return Ok(Value::new_array(values, array_type, self.mutable)?.into());
+        Ok(Value::new_array(values, array_type, self.mutable)?)
     }
 
     fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
@@ -169,7 +192,14 @@ impl ExpressionComponent for ArrayExpression {
         let Some(array_type) = is_duplicates(types) else {
             return Err("Expected all values of an array to be the same type".into());
         };
+        self.element_type.replace(Some(array_type.clone()));
 
         return Ok(Type::Array(Box::new(array_type), self.mutable));
     }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        for value in &self.values {
+            value.walk(visitor);
+        }
+    }
 }
@@ -0,0 +1,73 @@
+use serde::Serialize;
+
+use crate::{
+    ast::Visitor,
+    components::{
+        builtins,
+        root::type_definition::TypeDefinition,
+        stack::Stack,
+        values::{Type, Value},
+        EvaluationResult, PostProcessContext, Tokens,
+    },
+    constants::DEFAULT,
+    errors::PostProcessError,
+    lexer::{Token, TokenValue},
+    utils::iterators::Backtrackable,
+    Executor, ParserError,
+};
+
+use super::ExpressionComponent;
+
+/// `default(<type>)` gives the zero value of a type, this can't be a normal builtin because it
+/// takes a type rather than a value and the result type depends on it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DefaultExpression {
+    pub value_type: TypeDefinition,
+}
+
+impl ExpressionComponent for DefaultExpression {
+    fn try_parse<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Result<Option<Self>, ParserError> {
+        let checkpoint = tokens.checkpoint();
+        let Some(TokenValue::Identifier(DEFAULT)) = tokens.next_value() else {
+            tokens.backtrack(checkpoint);
+            return Ok(None);
+        };
+        let Some(TokenValue::LeftBracket()) = tokens.next_value() else {
+            tokens.backtrack(checkpoint);
+            return Ok(None);
+        };
+
+        // Anything that isn't a type is left to be parsed as a call to a function named default
+        let Ok(value_type) = TypeDefinition::parse(tokens) else {
+            tokens.backtrack(checkpoint);
+            return Ok(None);
+        };
+        let Some(TokenValue::RightBracket()) = tokens.next_value() else {
+            tokens.backtrack(checkpoint);
+            return Ok(None);
+        };
+
+        Ok(Some(Self { value_type }))
+    }
+
+    fn evaluate<E: Executor>(
+        &self,
+        _stack: &mut Stack,
+        _executor: &mut E,
+    ) -> EvaluationResult<Value> {
+        Ok(builtins::default_value(&self.value_type.value)?)
+    }
+
+    fn get_type(&self, _context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
+        let value_type = &self.value_type.value;
+        if builtins::default_value(value_type).is_err() {
+            return Err(format!("Type {value_type} does not have a default value").into());
+        }
+
+        Ok(value_type.clone())
+    }
+
+    fn walk(&self, _visitor: &mut dyn Visitor) {}
+}
@@ -1,6 +1,7 @@
 use serde::Serialize;
 
 use crate::{
+    ast::Visitor,
     components::{
         stack::Stack,
         values::{Type, Value},
@@ -12,7 +13,7 @@ use crate::{
     Executor, ParserError,
 };
 
-use super::{BaseExpression, DependentExpressionComponent, Expression};
+use super::{BaseExpression, DependentExpressionComponent, Expression, IntegerLiteral};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct IndexExpression {
@@ -20,6 +21,41 @@ pub struct IndexExpression {
     index: Box<Expression>,
 }
 
+impl IndexExpression {
+    /// Tuple items can have different types, so the index has to be known up front unless every
+    /// item has the same type.
+    fn get_tuple_item_type(&self, item_types: Vec<Type>) -> Result<Type, PostProcessError> {
+        if let Expression {
+            first: BaseExpression::IntegerLiteral(IntegerLiteral { value }),
+            operations,
+        } = self.index.as_ref()
+        {
+            if operations.is_empty() {
+                let len = item_types.len();
+                let item_type = usize::try_from(*value)
+                    .ok()
+                    .and_then(|index| item_types.into_iter().nth(index));
+                return item_type.ok_or(
+                    format!("Index {value} is out of bounds for a tuple of length {len}").into(),
+                );
+            }
+        }
+
+        let mut item_types = item_types.into_iter();
+        let first = item_types.next();
+        if let Some(first) = first {
+            if item_types.all(|item_type| item_type == first) {
+                return Ok(first);
+            }
+        }
+
+        Err(
+            "Tuples with items of different types can only be indexed with an integer literal"
+                .into(),
+        )
+    }
+}
+
 impl DependentExpressionComponent for IndexExpression {
     fn try_parse<'a, I: Iterator<Item = &'a Token<'a>>>(
         inner: BaseExpression,
@@ -51,33 +87,48 @@ impl DependentExpressionComponent for IndexExpression {
         let Value::Integer(index) = index else {
             return Err("Index expression must evaluate to integer".into());
         };
-        let index = TryInto::<usize>::try_into(index)
-            .map_err(|_| "Index expression must be a positive integer")?;
+        let index = usize::try_from(index)
+            .map_err(|_| format!("Cannot get element at negative index {index}"))?;
 
         let inner_value = self.inner.evaluate(stack, executor)?;
 
+        if let Value::Tuple(mut values) = inner_value {
+            let len = values.len();
+            let result = values.get_mut(index).ok_or(format!(
+                "Cannot get element at index {index} because tuple only has {len} elements"
+            ))?;
+
+            return Ok(core::mem::take(result));
+        }
+
         let Value::Array(array, _, _) = inner_value else {
-            return Err("Can only index values of type array".into());
+            return Err("Can only index values of type array or tuple".into());
         };
         let array = array.borrow();
         let result = array.get(index).ok_or(format!(
-            "Index value must be less than array length, array has length {} and got index {}.",
-            array.len(),
-            index
+            "Cannot get element at index {index} because array only has {} elements",
+            array.len()
         ))?;
 
         return Ok(result.clone());
     }
 
     fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
-        let Type::Array(inner_type, _) = self.inner.get_type(context)? else {
-            return Err("Expression must evaluate to an array".into());
-        };
+        let inner_type = self.inner.get_type(context)?;
 
         let Type::Integer = self.index.get_type(context)? else {
             return Err("Index expression must evaluate to integer".into());
         };
 
-        return Ok(*inner_type);
+        match inner_type {
+            Type::Array(inner_type, _) => Ok(*inner_type),
+            Type::Tuple(item_types) => self.get_tuple_item_type(item_types),
+            _ => Err("Expression must evaluate to an array or tuple".into()),
+        }
+    }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        self.inner.walk(visitor);
+        self.index.walk(visitor);
     }
 }
@@ -0,0 +1,77 @@
+use serde::Serialize;
+
+use crate::{
+    components::{
+        stack::Stack,
+        values::{Type, Value},
+        EvaluationResult, PostProcessContext, Tokens,
+    },
+    errors::{ParserError, PostProcessError},
+    lexer::{Token, TokenValue},
+    utils::iterators::Backtrackable,
+    Executor,
+};
+
+use super::{string::parse_string, ExpressionComponent};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CharLiteral {
+    pub value: char,
+}
+
+impl CharLiteral {
+    fn parse_impl<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Result<Self, ParserError> {
+        let mut value = String::new();
+        if let Some(TokenValue::StringLiteral(literal)) = tokens.peek_value() {
+            value = parse_string(literal)?;
+            tokens.next();
+        }
+
+        let Some(TokenValue::SingleQuote()) = tokens.next_value() else {
+            return Err("Unable to parse char literal".into());
+        };
+
+        let mut chars = value.chars();
+        let (Some(char), None) = (chars.next(), chars.next()) else {
+            return Err(format!(
+                "Char literal must contain exactly one character, but got '{value}'"
+            )
+            .into());
+        };
+
+        Ok(char.into())
+    }
+}
+
+impl From<char> for CharLiteral {
+    fn from(value: char) -> Self {
+        CharLiteral { value }
+    }
+}
+
+impl ExpressionComponent for CharLiteral {
+    fn try_parse<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Result<Option<Self>, ParserError> {
+        let Some(TokenValue::SingleQuote()) = tokens.peek_value() else {
+            return Ok(None);
+        };
+        tokens.next();
+
+        Ok(Some(Self::parse_impl(tokens)?))
+    }
+
+    fn evaluate<E: Executor>(
+        &self,
+        _stack: &mut Stack,
+        _context: &mut E,
+    ) -> EvaluationResult<Value> {
+        Ok(Value::Char(self.value))
+    }
+
+    fn get_type(&self, _context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
+        Ok(Type::Char)
+    }
+}
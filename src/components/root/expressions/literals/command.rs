@@ -1,6 +1,7 @@
 use serde::Serialize;
 
 use crate::{
+    ast::Visitor,
     components::{
         errors::ParserError,
         root::expressions::ExpressionComponent,
@@ -33,6 +34,12 @@ impl CommandLiteral {
     ) -> Result<Self, ParserError> {
         let command =
             Self::parse_next_literal(tokens)?.ok_or("Command literal must contain command")?;
+
+        // Programs with interpolated names can only be checked once they are run
+        if command.parts.is_empty() && command.end.trim().is_empty() {
+            return Err("Command literal must have a non-empty program name".into());
+        }
+
         let mut arguments = Vec::new();
         while let Some(next) = Self::parse_next_literal(tokens)? {
             arguments.push(next);
@@ -100,4 +107,11 @@ impl ExpressionComponent for CommandLiteral {
     fn get_type(&self, _context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
         return Ok(Type::Command);
     }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        self.command.walk(visitor);
+        for argument in &self.arguments {
+            argument.walk(visitor);
+        }
+    }
 }
@@ -1,7 +1,7 @@
 use crate::{
     components::{
         stack::Stack,
-        values::{Type, Value},
+        values::{Float, Type, Value},
         EvaluationResult, PostProcessContext, Tokens,
     },
     constants::{FALSE, TRUE},
@@ -11,9 +11,11 @@ use crate::{
     Executor,
 };
 
+mod char;
 mod command;
 mod string;
 
+pub use char::CharLiteral;
 pub use command::CommandLiteral;
 use serde::Serialize;
 pub use string::StringLiteral;
@@ -22,36 +24,70 @@ use super::ExpressionComponent;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct IntegerLiteral {
-    pub value: u32,
+    pub value: i32,
 }
 
 impl IntegerLiteral {
-    fn parse_impl(value: &str) -> Result<IntegerLiteral, ParserError> {
+    fn parse_impl(value: &str, negative: bool) -> Result<IntegerLiteral, ParserError> {
         let Ok(value) = u32::from_str_radix(value, 10) else {
             return Err(format!("Unable to parse {value} as a number").into());
         };
 
-        if TryInto::<i32>::try_into(value).is_err() {
-            return Err(format!("Number is out of range for an integer").into());
-        }
-        return Ok(value.into());
+        // The sign is parsed along with the number, so that the smallest integer can be written
+        let value = if negative {
+            0i32.checked_sub_unsigned(value)
+        } else {
+            i32::try_from(value).ok()
+        };
+        let Some(value) = value else {
+            return Err("Number is out of range for an integer".into());
+        };
+
+        Ok(value.into())
     }
 }
 
-impl From<u32> for IntegerLiteral {
-    fn from(value: u32) -> Self {
+impl From<i32> for IntegerLiteral {
+    fn from(value: i32) -> Self {
         IntegerLiteral { value }
     }
 }
 
+/// Consumes a - that is directly followed by a number, so that it becomes part of the number's
+/// literal instead of a negation. Whitespace in between (e.g. `- 1`) still means a negation.
+fn try_parse_sign<'a, I: Iterator<Item = &'a Token<'a>>>(tokens: &mut Backtrackable<I>) -> bool {
+    let checkpoint = tokens.checkpoint();
+    if let (
+        Some(Token {
+            value: TokenValue::Dash(),
+            end,
+            ..
+        }),
+        Some(Token {
+            value: TokenValue::IntegerLiteral(_),
+            start,
+            ..
+        }),
+    ) = (tokens.next(), tokens.peek())
+    {
+        if end == start {
+            return true;
+        }
+    }
+
+    tokens.backtrack(checkpoint);
+    false
+}
+
 impl ExpressionComponent for IntegerLiteral {
     fn try_parse<'a, I: Iterator<Item = &'a Token<'a>>>(
         tokens: &mut Backtrackable<I>,
     ) -> Result<Option<Self>, ParserError> {
+        let negative = try_parse_sign(tokens);
         Ok(
             if let Some(TokenValue::IntegerLiteral(value)) = tokens.peek_value() {
                 tokens.next();
-                Some(Self::parse_impl(value)?)
+                Some(Self::parse_impl(value, negative)?)
             } else {
                 None
             },
@@ -63,7 +99,7 @@ impl ExpressionComponent for IntegerLiteral {
         _stack: &mut Stack,
         _context: &mut E,
     ) -> EvaluationResult<Value> {
-        Ok(Value::Integer(self.value.try_into().unwrap()).into())
+        Ok(Value::Integer(self.value))
     }
 
     fn get_type(&self, _context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
@@ -71,6 +107,69 @@ impl ExpressionComponent for IntegerLiteral {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FloatLiteral {
+    pub value: Float,
+}
+
+impl ExpressionComponent for FloatLiteral {
+    fn try_parse<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Result<Option<Self>, ParserError> {
+        // Floats are written as two integers joined by a dot with no whitespace in between, so that
+        // ranges (1..2) and calls on integers (1.fmt()) are left alone
+        let checkpoint = tokens.checkpoint();
+        let negative = try_parse_sign(tokens);
+        let (
+            Some(Token {
+                value: TokenValue::IntegerLiteral(whole),
+                end,
+                ..
+            }),
+            Some(
+                dot @ Token {
+                    value: TokenValue::Dot(),
+                    ..
+                },
+            ),
+            Some(Token {
+                value: TokenValue::IntegerLiteral(fraction),
+                start,
+                ..
+            }),
+        ) = (tokens.next(), tokens.next(), tokens.next())
+        else {
+            tokens.backtrack(checkpoint);
+            return Ok(None);
+        };
+
+        if *end != dot.start || dot.end != *start {
+            tokens.backtrack(checkpoint);
+            return Ok(None);
+        }
+
+        let sign = if negative { "-" } else { "" };
+        let value = format!("{sign}{whole}.{fraction}");
+        let Some(value) = value.parse().ok().and_then(Float::new) else {
+            return Err(format!("Unable to parse {value} as a float").into());
+        };
+
+        Ok(Some(Self { value }))
+    }
+
+    fn evaluate<E: Executor>(
+        &self,
+        _stack: &mut Stack,
+        _context: &mut E,
+    ) -> EvaluationResult<Value> {
+        Ok(Value::Float(self.value))
+    }
+
+    fn get_type(&self, _context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
+        Ok(Type::Float)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct BooleanLiteral {
     pub value: bool,
@@ -1,8 +1,11 @@
+use std::{iter::Peekable, str::Chars};
+
 use serde::Serialize;
-use unicode_segmentation::UnicodeSegmentation;
 
 use crate::{
+    ast::Visitor,
     components::{
+        builtins,
         errors::ParserError,
         root::expressions::{Expression, ExpressionComponent},
         stack::Stack,
@@ -44,7 +47,7 @@ impl StringLiteral {
                 };
                 let value = Expression::parse(tokens)?;
                 let Some(TokenValue::RightCurly()) = tokens.next_value() else {
-                    return Err("Expected } after template variable".into());
+                    return Err("Expected } at the end of template expression".into());
                 };
                 variables.push((end, value.into()));
                 end = String::new();
@@ -56,6 +59,25 @@ impl StringLiteral {
         return Ok(StringLiteral::new(variables, end));
     }
 
+    /// Raw strings can't contain templates or escapes, so their contents are used as is.
+    fn parse_raw<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Result<Self, ParserError> {
+        let mut value = "";
+        if let Some(TokenValue::StringLiteral(literal)) = tokens.peek_value() {
+            value = literal;
+            tokens.next();
+        }
+
+        let Some(TokenValue::DoubleQuote()) = tokens.next_value() else {
+            return Err("Unable to parse raw string literal".into());
+        };
+
+        Ok(value.into())
+    }
+
+    /// Template expressions can be of any type, they are formatted the same way as `str` so that
+    /// strings are inserted without quotes.
     pub fn resolve<E: Executor>(
         &self,
         stack: &mut Stack,
@@ -64,10 +86,7 @@ impl StringLiteral {
         let mut result = String::new();
         for (prefix, expression) in &self.parts {
             result += &prefix;
-            let Value::String(variable_value) = expression.evaluate(stack, executor)? else {
-                return Err("Template variable in strings must resolve to a string".into());
-            };
-            result += &variable_value;
+            result += &builtins::format_unquoted(&expression.evaluate(stack, executor)?);
         }
         result += &self.end;
         Ok(result)
@@ -90,14 +109,17 @@ impl ExpressionComponent for StringLiteral {
     fn try_parse<'a, I: Iterator<Item = &'a Token<'a>>>(
         tokens: &mut Backtrackable<I>,
     ) -> Result<Option<Self>, ParserError> {
-        Ok(
-            if let Some(TokenValue::DoubleQuote()) = tokens.peek_value() {
+        Ok(match tokens.peek_value() {
+            Some(TokenValue::DoubleQuote()) => {
                 tokens.next();
                 Some(Self::parse_impl(tokens)?)
-            } else {
-                None
-            },
-        )
+            }
+            Some(TokenValue::RawQuote()) => {
+                tokens.next();
+                Some(Self::parse_raw(tokens)?)
+            }
+            _ => None,
+        })
     }
     fn evaluate<E: Executor>(
         &self,
@@ -107,26 +129,76 @@ impl ExpressionComponent for StringLiteral {
         Ok(Value::String(self.resolve(stack, executor)?).into())
     }
 
-    fn get_type(&self, _context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
+    fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
+        for (_, expression) in &self.parts {
+            if expression.get_type(context)? == Type::Void {
+                return Err("Template expressions in strings must not be void".into());
+            }
+        }
+
         return Ok(Type::String);
     }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        for (_, expression) in &self.parts {
+            expression.walk(visitor);
+        }
+    }
 }
 
-fn parse_string(value: &str) -> Result<String, ParserError> {
+/// Interprets the escape sequences in a string literal, the lexer has already made sure that a
+/// backslash is always followed by something. Char literals share the same escapes.
+pub(super) fn parse_string(value: &str) -> Result<String, ParserError> {
     let mut result = String::new();
-    let mut escape = false;
-    for grapheme in value.graphemes(true) {
-        if grapheme == "\\" && !escape {
-            escape = true;
-        } else {
-            result += grapheme;
-            escape = false;
+    let mut chars = value.chars().peekable();
+    while let Some(next) = chars.next() {
+        if next != '\\' {
+            result.push(next);
+            continue;
         }
-    }
 
-    if escape {
-        return Err(format!("Unterminated escape sequence in \"{value}\"").into());
+        let escaped = match chars.next() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('\'') => '\'',
+            Some('$') => '$',
+            Some('u') => parse_unicode_escape(&mut chars)?,
+            Some(next) => {
+                return Err(format!("Unknown escape sequence \\{next} in string").into());
+            }
+            None => {
+                return Err(format!("Unterminated escape sequence in \"{value}\"").into());
+            }
+        };
+        result.push(escaped);
     }
 
     return Ok(result);
 }
+
+/// Parses the `{1F600}` part of a `\u{1F600}` escape into the character it refers to.
+fn parse_unicode_escape(chars: &mut Peekable<Chars>) -> Result<char, ParserError> {
+    let Some('{') = chars.next() else {
+        return Err("Expected { after \\u in string".into());
+    };
+
+    let mut digits = String::new();
+    loop {
+        match chars.next() {
+            Some('}') => break,
+            Some(next) => digits.push(next),
+            None => return Err("Expected } after unicode escape in string".into()),
+        }
+    }
+
+    let is_valid = (1..=6).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_hexdigit());
+    u32::from_str_radix(&digits, 16)
+        .ok()
+        .filter(|_| is_valid)
+        .and_then(char::from_u32)
+        .ok_or_else(|| format!("Invalid unicode escape \\u{{{digits}}} in string").into())
+}
@@ -1,14 +1,16 @@
 use serde::Serialize;
 
 use crate::{
+    ast::Visitor,
     components::{
+        builtins,
         root::identifier::Identifier,
         stack::Stack,
         values::{Type, Value},
         ControlFlowOptions, EvaluationException, EvaluationResult, PostProcessContext, ScopeType,
         Tokens,
     },
-    constants::{FOR, IN, WHILE},
+    constants::{DO, FOR, IN, WHILE},
     errors::PostProcessError,
     lexer::{Token, TokenValue},
     utils::iterators::Backtrackable,
@@ -17,23 +19,89 @@ use crate::{
 
 use super::{Block, Expression, ExpressionComponent};
 
+/// Parses the optional `label:` in front of a loop, so that `break label;` can be used to break
+/// out of it from inside a nested loop.
+fn try_parse_label<'a, I: Iterator<Item = &'a Token<'a>>>(
+    tokens: &mut Backtrackable<I>,
+    keyword: &str,
+) -> Option<Option<Identifier>> {
+    if let Some(TokenValue::Keyword(next)) = tokens.peek_value() {
+        if *next == keyword {
+            tokens.next();
+            return Some(None);
+        }
+    }
+
+    let Some(TokenValue::Identifier(label)) = tokens.next_value() else {
+        return None;
+    };
+    let Some(TokenValue::Colon()) = tokens.next_value() else {
+        return None;
+    };
+    let Some(TokenValue::Keyword(next)) = tokens.next_value() else {
+        return None;
+    };
+    if *next != keyword {
+        return None;
+    }
+
+    Some(Some((*label).into()))
+}
+
+/// Works out if a loop should handle a break or continue, or if it is meant for an outer loop.
+fn matches_label(label: &Option<String>, loop_label: &Option<Identifier>) -> bool {
+    match (label, loop_label) {
+        (None, _) => true,
+        (Some(label), Some(loop_label)) => *label == loop_label.value,
+        (Some(_), None) => false,
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct ForLoopExpression {
+    label: Option<Identifier>,
+    index_name: Option<Identifier>,
     item_name: Identifier,
     array_expression: Box<Expression>,
     loop_body: Block,
 }
 
+impl ForLoopExpression {
+    /// Parses either `item` or `(index, item)` as the names to bind for each item of the array.
+    fn parse_names<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Result<(Option<Identifier>, Identifier), ParserError> {
+        let Some(TokenValue::LeftBracket()) = tokens.peek_value() else {
+            let Some(TokenValue::Identifier(item_name)) = tokens.next_value() else {
+                return Err("expected identifier".into());
+            };
+            return Ok((None, (*item_name).into()));
+        };
+        tokens.next();
+
+        let Some(TokenValue::Identifier(index_name)) = tokens.next_value() else {
+            return Err("expected identifier for the index of the for loop".into());
+        };
+        let Some(TokenValue::Comma()) = tokens.next_value() else {
+            return Err("expected , after the index of the for loop".into());
+        };
+        let Some(TokenValue::Identifier(item_name)) = tokens.next_value() else {
+            return Err("expected identifier for the item of the for loop".into());
+        };
+        let Some(TokenValue::RightBracket()) = tokens.next_value() else {
+            return Err("expected ) after the item of the for loop".into());
+        };
+
+        Ok((Some((*index_name).into()), (*item_name).into()))
+    }
+}
+
 impl ExpressionComponent for ForLoopExpression {
     fn try_parse<'a, I: Iterator<Item = &'a crate::lexer::Token<'a>>>(
         tokens: &mut crate::utils::iterators::Backtrackable<I>,
     ) -> Result<Option<Self>, crate::errors::ParserError> {
-        if let Some(TokenValue::Keyword(FOR)) = tokens.peek_value() {
-            tokens.next();
-
-            let Some(TokenValue::Identifier(item_name)) = tokens.next_value() else {
-                return Err("expected identifier".into());
-            };
+        if let Some(label) = tokens.backtrack_if_none(|tokens| try_parse_label(tokens, FOR)) {
+            let (index_name, item_name) = Self::parse_names(tokens)?;
 
             let Some(TokenValue::Keyword(IN)) = tokens.next_value() else {
                 return Err("expected keyword in".into());
@@ -43,7 +111,9 @@ impl ExpressionComponent for ForLoopExpression {
             let loop_body = Block::parse(tokens)?;
 
             return Ok(Some(ForLoopExpression {
-                item_name: (*item_name).into(),
+                label,
+                index_name,
+                item_name,
                 array_expression: Box::new(array_expression),
                 loop_body,
             }));
@@ -61,22 +131,35 @@ impl ExpressionComponent for ForLoopExpression {
             return Err("for ... in loop must be used on an array value".into());
         };
 
-        for item in array.as_ref().borrow().iter() {
+        for (index, item) in array.as_ref().borrow().iter().enumerate() {
             let result = self.loop_body.execute_with_initializer(
-                |stack| stack.declare_variable_init(&self.item_name.value, item.clone(), false),
+                |stack| {
+                    if let Some(index_name) = &self.index_name {
+                        stack.declare_variable_init(
+                            &index_name.value,
+                            builtins::array_index(index)?,
+                            false,
+                        )?;
+                    }
+                    stack.declare_variable_init(&self.item_name.value, item.clone(), false)
+                },
                 stack,
                 executor,
             );
 
-            if let Err(EvaluationException::ControlFlow(ControlFlowOptions::Break())) = result {
-                break;
-            }
-
-            if let Err(EvaluationException::ControlFlow(ControlFlowOptions::Continue())) = result {
-                continue;
-            }
-
-            result?;
+            match result {
+                Err(EvaluationException::ControlFlow(ControlFlowOptions::Break(label)))
+                    if matches_label(&label, &self.label) =>
+                {
+                    break
+                }
+                Err(EvaluationException::ControlFlow(ControlFlowOptions::Continue(label)))
+                    if matches_label(&label, &self.label) =>
+                {
+                    continue
+                }
+                result => result?,
+            };
         }
 
         Ok(Value::Void.into())
@@ -89,19 +172,28 @@ impl ExpressionComponent for ForLoopExpression {
 
         self.loop_body.post_process_with_initializer(
             |context| {
+                if let Some(index_name) = &self.index_name {
+                    context.declare_variable(index_name.value.clone(), Type::Integer);
+                }
                 context.declare_variable(self.item_name.value.clone(), *inner_type);
                 Ok(())
             },
-            ScopeType::Looped,
+            ScopeType::Looped(self.label.as_ref().map(|label| label.value.clone())),
             context,
         )?;
 
         Ok(Type::Void)
     }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        self.array_expression.walk(visitor);
+        self.loop_body.walk(visitor);
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct WhileLoopExpression {
+    label: Option<Identifier>,
     check_expression: Box<Expression>,
     loop_body: Block,
 }
@@ -110,13 +202,12 @@ impl ExpressionComponent for WhileLoopExpression {
     fn try_parse<'a, I: Iterator<Item = &'a Token<'a>>>(
         tokens: &mut Backtrackable<I>,
     ) -> Result<Option<Self>, ParserError> {
-        if let Some(TokenValue::Keyword(WHILE)) = tokens.peek_value() {
-            tokens.next();
-
+        if let Some(label) = tokens.backtrack_if_none(|tokens| try_parse_label(tokens, WHILE)) {
             let check_expression = Expression::parse(tokens)?;
             let loop_body = Block::parse(tokens)?;
 
             return Ok(Some(WhileLoopExpression {
+                label,
                 check_expression: Box::new(check_expression),
                 loop_body,
             }));
@@ -140,27 +231,121 @@ impl ExpressionComponent for WhileLoopExpression {
                 return Ok(Value::Void.into());
             }
 
-            let result = self.loop_body.execute(stack, executor);
-            if let Err(EvaluationException::ControlFlow(ControlFlowOptions::Break())) = result {
-                return Ok(Value::Void.into());
-            }
+            match self.loop_body.execute(stack, executor) {
+                Err(EvaluationException::ControlFlow(ControlFlowOptions::Break(label)))
+                    if matches_label(&label, &self.label) =>
+                {
+                    return Ok(Value::Void)
+                }
+                Err(EvaluationException::ControlFlow(ControlFlowOptions::Continue(label)))
+                    if matches_label(&label, &self.label) =>
+                {
+                    continue
+                }
+                result => result?,
+            };
+        }
+    }
 
-            if let Err(EvaluationException::ControlFlow(ControlFlowOptions::Continue())) = result {
-                continue;
-            }
+    fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
+        let Type::Boolean = self.check_expression.get_type(context)? else {
+            return Err("This expression must resolve to a boolean".into());
+        };
+
+        let label = self.label.as_ref().map(|label| label.value.clone());
+        self.loop_body.post_process_with_initializer(
+            |_| Ok(()),
+            ScopeType::Looped(label),
+            context,
+        )?;
+
+        Ok(Type::Void)
+    }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        self.check_expression.walk(visitor);
+        self.loop_body.walk(visitor);
+    }
+}
+
+/// Like a while loop, but the check happens after the body so the body always runs at least once.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DoWhileLoopExpression {
+    label: Option<Identifier>,
+    loop_body: Block,
+    check_expression: Box<Expression>,
+}
+
+impl ExpressionComponent for DoWhileLoopExpression {
+    fn try_parse<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Result<Option<Self>, ParserError> {
+        if let Some(label) = tokens.backtrack_if_none(|tokens| try_parse_label(tokens, DO)) {
+            let loop_body = Block::parse(tokens)?;
+
+            let Some(TokenValue::Keyword(WHILE)) = tokens.next_value() else {
+                return Err("do block must be followed by while".into());
+            };
+            let check_expression = Expression::parse(tokens)?;
+
+            return Ok(Some(DoWhileLoopExpression {
+                label,
+                loop_body,
+                check_expression: Box::new(check_expression),
+            }));
+        }
+
+        Ok(None)
+    }
 
-            result?;
+    fn evaluate<E: Executor>(
+        &self,
+        stack: &mut Stack,
+        executor: &mut E,
+    ) -> EvaluationResult<Value> {
+        loop {
+            match self.loop_body.execute(stack, executor) {
+                Err(EvaluationException::ControlFlow(ControlFlowOptions::Break(label)))
+                    if matches_label(&label, &self.label) =>
+                {
+                    return Ok(Value::Void)
+                }
+                // Continuing still goes through the check, the same as in a while loop
+                Err(EvaluationException::ControlFlow(ControlFlowOptions::Continue(label)))
+                    if matches_label(&label, &self.label) => {}
+                result => {
+                    result?;
+                }
+            };
+
+            let Value::Boolean(check_result) = self.check_expression.evaluate(stack, executor)?
+            else {
+                return Err("do while loop check expression must return a boolean value".into());
+            };
+
+            if !check_result {
+                return Ok(Value::Void);
+            }
         }
     }
 
     fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
+        let label = self.label.as_ref().map(|label| label.value.clone());
+        self.loop_body.post_process_with_initializer(
+            |_| Ok(()),
+            ScopeType::Looped(label),
+            context,
+        )?;
+
         let Type::Boolean = self.check_expression.get_type(context)? else {
             return Err("This expression must resolve to a boolean".into());
         };
 
-        self.loop_body
-            .post_process_with_initializer(|_| Ok(()), ScopeType::Looped, context)?;
-
         Ok(Type::Void)
     }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        self.loop_body.walk(visitor);
+        self.check_expression.walk(visitor);
+    }
 }
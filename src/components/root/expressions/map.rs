@@ -0,0 +1,192 @@
+use std::cell::RefCell;
+
+use serde::Serialize;
+
+use crate::{
+    ast::Visitor,
+    components::{
+        root::type_definition::TypeDefinition,
+        stack::Stack,
+        values::{Type, Value},
+        EvaluationResult, PostProcessContext, Tokens,
+    },
+    constants::MUT,
+    errors::PostProcessError,
+    lexer::{Token, TokenValue},
+    utils::iterators::{is_duplicates, Backtrackable},
+    Executor, ParserError,
+};
+
+use super::{BaseExpression, Expression, ExpressionComponent};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MapExpression {
+    entries: Vec<(Expression, Expression)>,
+    mutable: bool,
+    // The value type of an empty map, which is written as `{: type}`
+    empty_type: Option<TypeDefinition>,
+    // The type checked value type, see `ArrayExpression`
+    #[serde(skip)]
+    value_type: RefCell<Option<Type>>,
+}
+
+impl MapExpression {
+    fn try_parse_entry<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Option<(Expression, Expression)> {
+        let key = Expression::parse(tokens).ok()?;
+        let Some(TokenValue::Colon()) = tokens.next_value() else {
+            return None;
+        };
+
+        Some((key, Expression::parse(tokens).ok()?))
+    }
+
+    /// Gets the key of an entry if it is a plain string literal, so that duplicates can be found
+    /// before the map is evaluated.
+    fn literal_key(key: &Expression) -> Option<&str> {
+        let (BaseExpression::StringLiteral(literal), []) = (&key.first, key.operations.as_slice())
+        else {
+            return None;
+        };
+
+        literal.parts.is_empty().then_some(literal.end.as_str())
+    }
+}
+
+impl ExpressionComponent for MapExpression {
+    fn try_parse<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Result<Option<Self>, ParserError> {
+        let checkpoint = tokens.checkpoint();
+        let mutable = if let Some(TokenValue::Keyword(MUT)) = tokens.peek_value() {
+            tokens.next();
+            true
+        } else {
+            false
+        };
+
+        let Some(TokenValue::LeftCurly()) = tokens.peek_value() else {
+            tokens.backtrack(checkpoint);
+            return Ok(None);
+        };
+        tokens.next();
+
+        if let Some(TokenValue::Colon()) = tokens.peek_value() {
+            tokens.next();
+            let empty_type = TypeDefinition::parse(tokens)?;
+            let Some(TokenValue::RightCurly()) = tokens.next_value() else {
+                return Err("Expected } after type of empty map".into());
+            };
+
+            return Ok(Some(Self {
+                entries: Vec::new(),
+                mutable,
+                empty_type: Some(empty_type),
+                value_type: RefCell::new(None),
+            }));
+        }
+
+        // Maps and blocks both start with {, so we only treat this as a map if the first item
+        // looks like a `key: value` entry, otherwise it gets left for the block expression.
+        let Some(first) = tokens.backtrack_if_none(Self::try_parse_entry) else {
+            tokens.backtrack(checkpoint);
+            return Ok(None);
+        };
+
+        let mut entries = vec![first];
+        loop {
+            let next = tokens.next_value();
+            if let Some(TokenValue::RightCurly()) = next {
+                break;
+            }
+
+            let Some(TokenValue::Comma()) = next else {
+                return Err("Expected , or } after each entry of map".into());
+            };
+
+            // Allow omitting the trailing comma
+            if let Some(TokenValue::RightCurly()) = tokens.peek_value() {
+                tokens.next();
+                break;
+            }
+
+            let key = Expression::parse(tokens)?;
+            let Some(TokenValue::Colon()) = tokens.next_value() else {
+                return Err("Expected : after key in map entry".into());
+            };
+            entries.push((key, Expression::parse(tokens)?));
+        }
+
+        Ok(Some(Self {
+            entries,
+            mutable,
+            empty_type: None,
+            value_type: RefCell::new(None),
+        }))
+    }
+
+    fn evaluate<E: Executor>(
+        &self,
+        stack: &mut Stack,
+        executor: &mut E,
+    ) -> EvaluationResult<Value> {
+        let mut entries = Vec::new();
+        for (key, value) in &self.entries {
+            let Value::String(key) = key.evaluate(stack, executor)? else {
+                return Err("Map keys must be strings".into());
+            };
+            if entries.iter().any(|(existing, _)| *existing == key) {
+                return Err(format!("Map contains duplicate key {key}").into());
+            }
+            entries.push((key, value.evaluate(stack, executor)?));
+        }
+
+        let map_type = self.value_type.borrow().clone();
+        let Some(map_type) = map_type
+            .or_else(|| self.empty_type.as_ref().map(|x| x.value.clone()))
+            .or_else(|| entries.first().map(|(_, value)| value.get_type()))
+        else {
+            return Err("Unable to determine map type for empty map".into());
+        };
+
+        Ok(Value::new_map(entries, map_type, self.mutable)?)
+    }
+
+    fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
+        if let Some(empty_type) = &self.empty_type {
+            self.value_type.replace(Some(empty_type.value.clone()));
+            return Ok(Type::Map(Box::new(empty_type.value.clone()), self.mutable));
+        }
+
+        let mut types = Vec::new();
+        let mut literal_keys = Vec::new();
+        for (key, value) in &self.entries {
+            let key_type = key.get_type(context)?;
+            if key_type != Type::String {
+                return Err(format!("Map keys must be strings, but got {key_type}").into());
+            }
+            if let Some(literal_key) = Self::literal_key(key) {
+                if literal_keys.contains(&literal_key) {
+                    return Err(format!("Map contains duplicate key {literal_key}").into());
+                }
+                literal_keys.push(literal_key);
+            }
+            types.push(value.get_type(context)?);
+        }
+
+        let Some(map_type) = is_duplicates(types) else {
+            return Err("Expected all values of a map to be the same type".into());
+        };
+        self.value_type.replace(Some(map_type.clone()));
+
+        Ok(Type::Map(Box::new(map_type), self.mutable))
+    }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        for (key, value) in &self.entries {
+            key.walk(visitor);
+            value.walk(visitor);
+        }
+    }
+}
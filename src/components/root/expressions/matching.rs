@@ -0,0 +1,175 @@
+use serde::Serialize;
+
+use crate::{
+    ast::Visitor,
+    components::{
+        root::operator::Operator,
+        stack::Stack,
+        values::{Type, Value},
+        EvaluationResult, PostProcessContext, Tokens,
+    },
+    constants::{MATCH, UNDERSCORE},
+    errors::PostProcessError,
+    lexer::{Token, TokenValue},
+    utils::iterators::{is_duplicates, Backtrackable},
+    Executor, ParserError,
+};
+
+use super::{BaseExpression, BooleanLiteral, Expression, ExpressionComponent};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum MatchPattern {
+    Value(Expression),
+    Wildcard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MatchExpression {
+    value: Box<Expression>,
+    arms: Vec<(MatchPattern, Expression)>,
+}
+
+impl MatchExpression {
+    fn parse_arm<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Result<(MatchPattern, Expression), ParserError> {
+        let pattern = if let Some(TokenValue::Identifier(UNDERSCORE)) = tokens.peek_value() {
+            tokens.next();
+            MatchPattern::Wildcard
+        } else {
+            MatchPattern::Value(Expression::parse(tokens)?)
+        };
+
+        let Some(TokenValue::Equals()) = tokens.next_value() else {
+            return Err("Expected => after match pattern".into());
+        };
+        let Some(TokenValue::RightAngle()) = tokens.next_value() else {
+            return Err("Expected => after match pattern".into());
+        };
+
+        Ok((pattern, Expression::parse(tokens)?))
+    }
+
+    /// Matches on booleans don't need a wildcard arm as long as both true and false are covered.
+    fn is_exhaustive(&self, value_type: &Type) -> bool {
+        let mut covers_true = false;
+        let mut covers_false = false;
+        for (pattern, _) in &self.arms {
+            match pattern {
+                MatchPattern::Wildcard => return true,
+                MatchPattern::Value(Expression {
+                    first: BaseExpression::BooleanLiteral(BooleanLiteral { value }),
+                    operations,
+                }) if operations.is_empty() => {
+                    covers_true |= *value;
+                    covers_false |= !*value;
+                }
+                _ => {}
+            }
+        }
+
+        *value_type == Type::Boolean && covers_true && covers_false
+    }
+}
+
+impl ExpressionComponent for MatchExpression {
+    fn try_parse<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Result<Option<Self>, ParserError> {
+        let Some(TokenValue::Keyword(MATCH)) = tokens.peek_value() else {
+            return Ok(None);
+        };
+        tokens.next();
+
+        let value = Expression::parse(tokens)?;
+        let Some(TokenValue::LeftCurly()) = tokens.next_value() else {
+            return Err("Expected { after match value".into());
+        };
+
+        let mut arms = Vec::new();
+        loop {
+            if let Some(TokenValue::RightCurly()) = tokens.peek_value() {
+                tokens.next();
+                break;
+            }
+
+            arms.push(Self::parse_arm(tokens)?);
+
+            let next = tokens.next_value();
+            if let Some(TokenValue::RightCurly()) = next {
+                break;
+            }
+
+            let Some(TokenValue::Comma()) = next else {
+                return Err("Expected , or } after each arm of match".into());
+            };
+        }
+
+        Ok(Some(Self {
+            value: Box::new(value),
+            arms,
+        }))
+    }
+
+    fn evaluate<E: Executor>(
+        &self,
+        stack: &mut Stack,
+        executor: &mut E,
+    ) -> EvaluationResult<Value> {
+        let value = self.value.evaluate(stack, executor)?;
+        for (pattern, result) in &self.arms {
+            let is_match = match pattern {
+                MatchPattern::Wildcard => true,
+                MatchPattern::Value(pattern) => {
+                    let pattern = pattern.evaluate(stack, executor)?;
+                    Operator::Equal.execute(value.clone(), pattern)? == Value::Boolean(true)
+                }
+            };
+
+            if is_match {
+                return result.evaluate(stack, executor);
+            }
+        }
+
+        Err(format!("No match arm found for value {value}").into())
+    }
+
+    fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
+        let value_type = self.value.get_type(context)?;
+
+        let mut types = Vec::new();
+        for (pattern, result) in &self.arms {
+            if let MatchPattern::Value(pattern) = pattern {
+                let pattern_type = pattern.get_type(context)?;
+                if pattern_type != value_type {
+                    return Err(format!(
+                        "Match pattern of type {pattern_type} can not match value of type {value_type}"
+                    )
+                    .into());
+                }
+            }
+
+            types.push(result.get_type(context)?);
+        }
+
+        if !self.is_exhaustive(&value_type) {
+            return Err("Match must have a _ arm unless every value is covered".into());
+        }
+
+        let Some(result_type) = is_duplicates(types) else {
+            return Err("Expected all arms of a match to be the same type".into());
+        };
+
+        Ok(result_type)
+    }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        self.value.walk(visitor);
+        for (pattern, result) in &self.arms {
+            if let MatchPattern::Value(pattern) = pattern {
+                pattern.walk(visitor);
+            }
+            result.walk(visitor);
+        }
+    }
+}
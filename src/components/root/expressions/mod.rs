@@ -1,11 +1,12 @@
 use crate::{
+    ast::Visitor,
     components::{
         stack::Stack,
         values::{Type, Value},
-        EvaluationResult, PostProcessContext,
+        EvaluationResult, PostProcessContext, Tokens,
     },
     errors::PostProcessError,
-    lexer::Token,
+    lexer::{Token, TokenValue},
     utils::iterators::Backtrackable,
     Executor, ParserError,
 };
@@ -15,25 +16,39 @@ mod block;
 mod brackets;
 mod branch;
 mod collections;
+mod default;
 mod index;
 mod literals;
 mod loops;
+mod map;
+mod matching;
+mod negation;
+mod not;
 mod pipeline;
+mod range;
 mod variable;
 
 use super::{block::Block, operator::Operator};
 
-use accessor::AccessorExpression;
-use block::BlockExpression;
-use brackets::BracketExpression;
-use branch::BranchExpression;
-use collections::{ArrayExpression, TupleExpression};
-use index::IndexExpression;
-use literals::{BooleanLiteral, CommandLiteral, IntegerLiteral, StringLiteral};
-use loops::{ForLoopExpression, WhileLoopExpression};
-use pipeline::PipelineExpression;
+pub use accessor::AccessorExpression;
+pub use block::BlockExpression;
+pub use brackets::BracketExpression;
+pub use branch::BranchExpression;
+pub use collections::{ArrayExpression, TupleExpression};
+pub use default::DefaultExpression;
+pub use index::IndexExpression;
+pub use literals::{
+    BooleanLiteral, CharLiteral, CommandLiteral, FloatLiteral, IntegerLiteral, StringLiteral,
+};
+pub use loops::{DoWhileLoopExpression, ForLoopExpression, WhileLoopExpression};
+pub use map::MapExpression;
+pub use matching::MatchExpression;
+pub use negation::NegationExpression;
+pub use not::NotExpression;
+pub use pipeline::PipelineExpression;
+pub use range::RangeExpression;
 use serde::Serialize;
-use variable::VariableExpression;
+pub use variable::VariableExpression;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Expression {
@@ -63,37 +78,59 @@ impl Expression {
 
     pub fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
         let mut left = self.first.get_type(context)?;
+        let mut previous: Option<&Operator> = None;
         for (operator, right) in &self.operations {
+            if let Some(previous) = previous {
+                if !previous.chains_with(operator) {
+                    return Err(format!(
+                        "Chaining {previous:?} with {operator:?} is not supported, add brackets to make the order of operations explicit."
+                    )
+                    .into());
+                }
+            }
+
             let right = right.get_type(context)?;
             left = operator.get_type(left, right)?;
+            previous = Some(operator);
         }
 
         return Ok(left);
     }
 
+    /// Gets the type of an expression whose value is not used, which is the same as `get_type`
+    /// except that an if does not need an else branch.
+    pub(crate) fn get_statement_type(
+        &self,
+        context: &mut PostProcessContext,
+    ) -> Result<Type, PostProcessError> {
+        if let (BaseExpression::BranchExpression(branch), []) =
+            (&self.first, self.operations.as_slice())
+        {
+            return branch.get_statement_type(context);
+        }
+
+        self.get_type(context)
+    }
+
     pub fn evaluate<E: Executor>(
         &self,
         stack: &mut Stack,
         executor: &mut E,
     ) -> EvaluationResult<Value> {
         let mut result = self.first.evaluate(stack, executor)?;
-        let mut previous: Option<&Operator> = None;
-
         for (operator, expression) in &self.operations {
             let right = expression.evaluate(stack, executor)?;
-            if let Some(previous) = previous {
-                if !previous.chains_with(operator) {
-                    return Err(format!(
-                        "Chaining {previous:?} with {operator:?} is not supported."
-                    ))?;
-                }
-            }
-
             result = operator.execute(result, right)?;
-            previous = Some(operator);
         }
         return Ok(result.into());
     }
+
+    pub(crate) fn walk(&self, visitor: &mut dyn Visitor) {
+        self.first.walk(visitor);
+        for (_, expression) in &self.operations {
+            expression.walk(visitor);
+        }
+    }
 }
 
 macro_rules! expression_content {
@@ -162,6 +199,18 @@ macro_rules! expression_content {
                     )*
                 }
             }
+
+            pub(crate) fn walk(&self, visitor: &mut dyn Visitor) {
+                visitor.visit_expression(self);
+                match self {
+                    $(
+                        Self::$expression_type(value) => value.walk(visitor),
+                    )*
+                    $(
+                        Self::$dependent_expression(value) => value.walk(visitor),
+                    )*
+                }
+            }
         }
     };
 }
@@ -169,36 +218,79 @@ macro_rules! expression_content {
 expression_content!(
     [
         StringLiteral,
+        CharLiteral,
         BooleanLiteral,
+        // Note: floats must be matched before integers, as they start with an integer.
+        FloatLiteral,
         IntegerLiteral,
         CommandLiteral,
+        // Note: maps must be matched before arrays and blocks, they share the mut keyword with arrays
+        // and the { token with blocks.
+        MapExpression,
         ArrayExpression,
         // Note: brackets must be matched before tuples, as the tuple matcher will also match expressions
         // that should be bracket expressions.
         BracketExpression,
         TupleExpression,
-        VariableExpression,
-        PipelineExpression,
+        // Note: default must be matched before variables, otherwise it would be parsed as a call
+        // to a function named default.
+        DefaultExpression,
+        // Note: loops must be matched before variables, as they can start with an identifier for
+        // their label.
         WhileLoopExpression,
+        DoWhileLoopExpression,
         ForLoopExpression,
+        VariableExpression,
+        PipelineExpression,
         BranchExpression,
+        MatchExpression,
         BlockExpression,
+        NegationExpression,
+        NotExpression,
     ],
     // These expressions are special as they all start with a BaseExpression, to avoid parsing the expressions multiple times
     // and to allow them to be nested within themselves, we have a special flow for them where they are matched after all
     // the other types.
-    [AccessorExpression, IndexExpression,]
+    [AccessorExpression, IndexExpression, RangeExpression,]
 );
 
 impl BaseExpression {
     fn parse<'a, I: Iterator<Item = &'a Token<'a>>>(
         tokens: &mut Backtrackable<I>,
+    ) -> Result<Self, ParserError> {
+        Self::parse_impl(tokens, true)
+    }
+
+    /// Parses the operand of a prefix operator such as - or !, if the next token is that
+    /// operator. Only the base expression is used so that -a + b is parsed as (-a) + b, this also
+    /// means --a is parsed as a double negation. Ranges bind looser than prefix operators too, so
+    /// they are left for the enclosing expression to parse (e.g. -1..2 is (-1)..2 rather than
+    /// -(1..2)).
+    fn try_parse_prefix_operand<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+        operator: TokenValue,
+    ) -> Result<Option<Box<Self>>, ParserError> {
+        if tokens.peek_value() != Some(&operator) {
+            return Ok(None);
+        }
+        tokens.next();
+
+        Ok(Some(Box::new(Self::parse_impl(tokens, false)?)))
+    }
+
+    fn parse_impl<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+        allow_ranges: bool,
     ) -> Result<Self, ParserError> {
         let Some(mut value) = Self::parse_inner(tokens)? else {
             return Err("Could not parse a valid expression".into());
         };
 
         loop {
+            if !allow_ranges && RangeExpression::is_next(tokens) {
+                return Ok(value);
+            }
+
             match Self::parse_dependent(value, tokens)? {
                 Ok(dependent_value) => value = dependent_value,
                 Err(value) => return Ok(value),
@@ -216,6 +308,9 @@ trait ExpressionComponent {
     fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError>;
     fn evaluate<E: Executor>(&self, stack: &mut Stack, executor: &mut E)
         -> EvaluationResult<Value>;
+    /// Walks any expressions nested inside this one, expressions without children can leave
+    /// this as a no-op.
+    fn walk(&self, _visitor: &mut dyn Visitor) {}
 }
 
 trait DependentExpressionComponent {
@@ -228,4 +323,5 @@ trait DependentExpressionComponent {
     fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError>;
     fn evaluate<E: Executor>(&self, stack: &mut Stack, executor: &mut E)
         -> EvaluationResult<Value>;
+    fn walk(&self, visitor: &mut dyn Visitor);
 }
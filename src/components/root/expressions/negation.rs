@@ -0,0 +1,62 @@
+use serde::Serialize;
+
+use crate::{
+    ast::Visitor,
+    components::{
+        stack::Stack,
+        values::{Float, Type, Value},
+        EvaluationResult, PostProcessContext,
+    },
+    errors::{ParserError, PostProcessError},
+    lexer::{Token, TokenValue},
+    utils::iterators::Backtrackable,
+    Executor,
+};
+
+use super::{BaseExpression, ExpressionComponent};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NegationExpression {
+    inner: Box<BaseExpression>,
+}
+
+impl ExpressionComponent for NegationExpression {
+    fn try_parse<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Result<Option<Self>, ParserError> {
+        let inner = BaseExpression::try_parse_prefix_operand(tokens, TokenValue::Dash())?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    fn evaluate<E: Executor>(
+        &self,
+        stack: &mut Stack,
+        executor: &mut E,
+    ) -> EvaluationResult<Value> {
+        let value = match self.inner.evaluate(stack, executor)? {
+            Value::Integer(value) => value,
+            // Floats are always finite, so negating one is too
+            Value::Float(value) => return Ok(Float::new(-value.value()).unwrap().into()),
+            _ => return Err("Only numbers can be negated".into()),
+        };
+
+        let Some(result) = value.checked_neg() else {
+            return Err(format!("Unable to negate {value} as the result is too large").into());
+        };
+
+        Ok(Value::Integer(result))
+    }
+
+    fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
+        let inner_type = self.inner.get_type(context)?;
+        let (Type::Integer | Type::Float) = inner_type else {
+            return Err(format!("Only numbers can be negated, but got {inner_type}").into());
+        };
+
+        Ok(inner_type)
+    }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        self.inner.walk(visitor);
+    }
+}
@@ -0,0 +1,57 @@
+use serde::Serialize;
+
+use crate::{
+    ast::Visitor,
+    components::{
+        stack::Stack,
+        values::{Type, Value},
+        EvaluationResult, PostProcessContext,
+    },
+    errors::{ParserError, PostProcessError},
+    lexer::{Token, TokenValue},
+    utils::iterators::Backtrackable,
+    Executor,
+};
+
+use super::{BaseExpression, ExpressionComponent};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NotExpression {
+    inner: Box<BaseExpression>,
+}
+
+impl ExpressionComponent for NotExpression {
+    fn try_parse<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Result<Option<Self>, ParserError> {
+        let inner = BaseExpression::try_parse_prefix_operand(tokens, TokenValue::Bang())?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    fn evaluate<E: Executor>(
+        &self,
+        stack: &mut Stack,
+        executor: &mut E,
+    ) -> EvaluationResult<Value> {
+        let Value::Boolean(value) = self.inner.evaluate(stack, executor)? else {
+            return Err("Only booleans can be inverted with !".into());
+        };
+
+        Ok(Value::Boolean(!value))
+    }
+
+    fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
+        let inner_type = self.inner.get_type(context)?;
+        let Type::Boolean = inner_type else {
+            return Err(
+                format!("Only booleans can be inverted with !, but got {inner_type}").into(),
+            );
+        };
+
+        Ok(inner_type)
+    }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        self.inner.walk(visitor);
+    }
+}
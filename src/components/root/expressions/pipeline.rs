@@ -1,14 +1,16 @@
 use serde::Serialize;
 
 use crate::{
+    ast::Visitor,
     components::{
+        builtins,
         root::identifier::Identifier,
         stack::Stack,
         values::{FileMode, Type, Value},
         EvaluationResult, PostProcessContext, Tokens,
     },
-    constants::{AS, CAP, EXEC},
-    errors::{ExecutionError, PostProcessError},
+    constants::{AS, CAP, EXEC, MERGE, SUBST},
+    errors::PostProcessError,
     executor::Executor,
     lexer::{Token, TokenValue},
     utils::iterators::Backtrackable,
@@ -20,8 +22,11 @@ use super::{Expression, ExpressionComponent};
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct PipelineCommand {
     expression: Expression,
+    substitutions: Vec<Expression>,
     capture_stderr: Option<Identifier>,
     capture_exit_code: Option<Identifier>,
+    capture_stdout: Option<Identifier>,
+    merge_stderr: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -34,8 +39,23 @@ impl PipelineCommand {
         tokens: &mut Backtrackable<I>,
     ) -> Result<Self, ParserError> {
         let expression = Expression::parse(tokens)?;
+
+        let mut substitutions = Vec::new();
+        while let Some(TokenValue::Identifier(SUBST)) = tokens.peek_value() {
+            tokens.next();
+            let Some(TokenValue::LeftBracket()) = tokens.next_value() else {
+                return Err("Expected ( after subst".into());
+            };
+            substitutions.push(Expression::parse(tokens)?);
+            let Some(TokenValue::RightBracket()) = tokens.next_value() else {
+                return Err("Expected ) after subst command".into());
+            };
+        }
+
         let mut capture_stderr = None;
         let mut capture_exit_code = None;
+        let mut capture_stdout = None;
+        let mut merge_stderr = false;
         if let Some(TokenValue::Pipe()) = tokens.peek_value() {
             tokens.next();
             loop {
@@ -44,15 +64,24 @@ impl PipelineCommand {
                     break;
                 }
 
-                let (identifier, alias) = Self::parse_option(tokens)?;
-                match identifier.value.as_str() {
-                    "exit_code" => capture_exit_code = Some(alias),
-                    "stderr" => capture_stderr = Some(alias),
-                    other => {
-                        return Err(format!(
-                            "Trying to capture unrecognized item: {other} in command options"
-                        )
-                        .into())
+                if let Some(TokenValue::Identifier(MERGE)) = tokens.peek_value() {
+                    tokens.next();
+                    let Some(TokenValue::Identifier("stderr")) = tokens.next_value() else {
+                        return Err("Expected stderr after merge in command options".into());
+                    };
+                    merge_stderr = true;
+                } else {
+                    let (identifier, alias) = Self::parse_option(tokens)?;
+                    match identifier.value.as_str() {
+                        "exit_code" => capture_exit_code = Some(alias),
+                        "stderr" => capture_stderr = Some(alias),
+                        "stdout" => capture_stdout = Some(alias),
+                        other => {
+                            return Err(format!(
+                                "Trying to capture unrecognized item: {other} in command options"
+                            )
+                            .into())
+                        }
                     }
                 }
 
@@ -68,18 +97,50 @@ impl PipelineCommand {
             }
         }
 
-        return Ok(Self {
+        if merge_stderr && capture_stderr.is_some() {
+            return Err("Can not both capture and merge stderr of a command".into());
+        }
+
+        Ok(Self {
             expression,
+            substitutions,
             capture_exit_code,
             capture_stderr,
-        });
+            capture_stdout,
+            merge_stderr,
+        })
+    }
+
+    fn to_command_definition<E: Executor>(
+        &self,
+        program: String,
+        arguments: Vec<String>,
+        stack: &mut Stack,
+        executor: &mut E,
+    ) -> EvaluationResult<CommandDefinition> {
+        let mut substitutions = Vec::new();
+        for substitution in &self.substitutions {
+            let Value::Command(program, arguments) = substitution.evaluate(stack, executor)? else {
+                return Err("Value provided to subst must be a Command".into());
+            };
+            substitutions.push(CommandDefinition::new(program, arguments, false));
+        }
+
+        Ok(CommandDefinition {
+            program,
+            arguments,
+            capture_stderr: self.capture_stderr.is_some(),
+            merge_stderr: self.merge_stderr,
+            capture_stdout: self.capture_stdout.is_some(),
+            substitutions,
+        })
     }
 
     fn parse_option<'a, I: Iterator<Item = &'a Token<'a>>>(
         tokens: &mut Backtrackable<I>,
     ) -> Result<(Identifier, Identifier), ParserError> {
         let Some(TokenValue::Keyword(CAP)) = tokens.peek_value() else {
-            return Err(format!("Expected cap after [ or , in command options").into());
+            return Err("Expected cap or merge after | or , in command options".into());
         };
         tokens.next();
 
@@ -114,15 +175,26 @@ impl ExpressionComponent for PipelineExpression {
         let mut commands = Vec::new();
         commands.push(PipelineCommand::parse(tokens)?);
 
-        loop {
-            let Some(TokenValue::Equals()) = tokens.peek_value() else {
-                break;
-            };
+        while let Some(Token {
+            value: TokenValue::Equals(),
+            end,
+            ..
+        }) = tokens.peek()
+        {
             tokens.next();
 
-            let Some(TokenValue::RightAngle()) = tokens.peek_value() else {
+            // Make sure there isn't any whitespace in the middle of =>
+            let Some(Token {
+                value: TokenValue::RightAngle(),
+                start,
+                ..
+            }) = tokens.peek()
+            else {
                 return Err(format!("Expected > after = in execute pipeline").into());
             };
+            if start != end {
+                return Err("Expected > after = in execute pipeline".into());
+            }
             tokens.next();
 
             commands.push(PipelineCommand::parse(tokens)?);
@@ -143,6 +215,18 @@ impl ExpressionComponent for PipelineExpression {
                     "Value provided to pipeline commands must be a FileHandle or Command".into(),
                 );
             }
+
+            if !command.substitutions.is_empty()
+                && command.expression.get_type(context)? != Type::Command
+            {
+                return Err("Only commands can be given subst arguments".into());
+            }
+
+            for substitution in &command.substitutions {
+                if substitution.get_type(context)? != Type::Command {
+                    return Err("Value provided to subst must be a Command".into());
+                }
+            }
         }
 
         // Declare variables after command expressions have been ran
@@ -153,11 +237,23 @@ impl ExpressionComponent for PipelineExpression {
             if let Some(capture_stderr) = &command.capture_stderr {
                 context.declare_variable(capture_stderr.value.clone(), Type::String);
             }
+            if let Some(capture_stdout) = &command.capture_stdout {
+                context.declare_variable(capture_stdout.value.clone(), Type::String);
+            }
         }
 
         return Ok(Type::String);
     }
 
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        for command in &self.commands {
+            command.expression.walk(visitor);
+            for substitution in &command.substitutions {
+                substitution.walk(visitor);
+            }
+        }
+    }
+
     fn evaluate<E: Executor>(
         &self,
         stack: &mut Stack,
@@ -185,11 +281,9 @@ impl ExpressionComponent for PipelineExpression {
                 }
             }
         } else if let Value::Command(program, arguments) = first_value {
-            pipeline.commands.push(CommandDefinition {
-                program,
-                arguments,
-                capture_stderr: first.capture_stderr.is_some(),
-            })
+            pipeline
+                .commands
+                .push(first.to_command_definition(program, arguments, stack, executor)?)
         } else {
             return Err(format!("Invalid type used in command pipeline").into());
         }
@@ -202,11 +296,9 @@ impl ExpressionComponent for PipelineExpression {
             }
             let command_value = command.expression.evaluate(stack, executor)?;
             if let Value::Command(program, arguments) = command_value {
-                pipeline.commands.push(CommandDefinition {
-                    program,
-                    arguments,
-                    capture_stderr: command.capture_stderr.is_some(),
-                })
+                pipeline
+                    .commands
+                    .push(command.to_command_definition(program, arguments, stack, executor)?)
             } else if let Value::FileHandle(path, mode) = command_value {
                 match mode {
                     FileMode::Write => {
@@ -215,11 +307,12 @@ impl ExpressionComponent for PipelineExpression {
                     FileMode::Append => {
                         pipeline.destination = Some(PipelineDestination::FileAppend(path))
                     }
+                    FileMode::Tee => pipeline.destination = Some(PipelineDestination::Tee(path)),
                     _ => {
-                        return Err(format!(
-                            "File must be in write or append mode to be used as a destination"
+                        return Err(
+                            "File must be in write, append or tee mode to be used as a destination"
+                                .into(),
                         )
-                        .into())
                     }
                 }
             } else {
@@ -227,11 +320,7 @@ impl ExpressionComponent for PipelineExpression {
             }
         }
 
-        let result = executor
-            .run_pipeline(&pipeline)
-            .map_err::<ExecutionError, _>(|err| {
-                format!("Error running command: {:}", err).into()
-            })?;
+        let result = builtins::run_pipeline(executor, &pipeline)?;
 
         let mut local_commands = self.commands.iter();
         if pipeline.source.is_some() {
@@ -239,6 +328,10 @@ impl ExpressionComponent for PipelineExpression {
         }
 
         for (command_output, command) in result.command_outputs.into_iter().zip(local_commands) {
+            if command_output.timed_out {
+                return Err(builtins::timeout_error(executor).into());
+            }
+
             if let Some(capture_exit_code) = &command.capture_exit_code {
                 stack.declare_variable_init(
                     &capture_exit_code.value,
@@ -260,6 +353,14 @@ impl ExpressionComponent for PipelineExpression {
                     false,
                 )?;
             }
+
+            if let Some(capture_stdout) = &command.capture_stdout {
+                stack.declare_variable_init(
+                    &capture_stdout.value,
+                    command_output.stdout.unwrap_or_default().into(),
+                    false,
+                )?;
+            }
         }
 
         return Ok(result.stdout.unwrap_or_default().into());
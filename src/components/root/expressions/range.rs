@@ -0,0 +1,106 @@
+use serde::Serialize;
+
+use crate::{
+    ast::Visitor,
+    components::{
+        stack::Stack,
+        values::{Type, Value},
+        EvaluationResult, PostProcessContext, Tokens,
+    },
+    errors::PostProcessError,
+    lexer::{Token, TokenValue},
+    utils::iterators::Backtrackable,
+    Executor, ParserError,
+};
+
+use super::{BaseExpression, DependentExpressionComponent};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RangeExpression {
+    start: Box<BaseExpression>,
+    end: Box<BaseExpression>,
+}
+
+impl RangeExpression {
+    pub(super) fn is_next<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> bool {
+        let checkpoint = tokens.checkpoint();
+        let result = matches!(
+            (tokens.next_value(), tokens.next_value()),
+            (Some(TokenValue::Dot()), Some(TokenValue::Dot()))
+        );
+        tokens.backtrack(checkpoint);
+
+        result
+    }
+}
+
+impl DependentExpressionComponent for RangeExpression {
+    fn try_parse<'a, I: Iterator<Item = &'a Token<'a>>>(
+        inner: BaseExpression,
+        tokens: &mut Backtrackable<I>,
+    ) -> Result<Result<Self, BaseExpression>, ParserError> {
+        let checkpoint = tokens.checkpoint();
+        let Some(Token {
+            value: TokenValue::Dot(),
+            end,
+            ..
+        }) = tokens.next()
+        else {
+            tokens.backtrack(checkpoint);
+            return Ok(Err(inner));
+        };
+
+        let Some(Token {
+            value: TokenValue::Dot(),
+            start,
+            ..
+        }) = tokens.next()
+        else {
+            tokens.backtrack(checkpoint);
+            return Ok(Err(inner));
+        };
+
+        if start != end {
+            return Err("Expected .. in range expression to not contain whitespace".into());
+        }
+
+        Ok(Ok(RangeExpression {
+            start: Box::new(inner),
+            end: Box::new(BaseExpression::parse(tokens)?),
+        }))
+    }
+
+    fn evaluate<E: Executor>(
+        &self,
+        stack: &mut Stack,
+        executor: &mut E,
+    ) -> EvaluationResult<Value> {
+        let Value::Integer(start) = self.start.evaluate(stack, executor)? else {
+            return Err("Start of range must evaluate to integer".into());
+        };
+        let Value::Integer(end) = self.end.evaluate(stack, executor)? else {
+            return Err("End of range must evaluate to integer".into());
+        };
+
+        // Ranges are exclusive of the end, so reversed ranges are just empty
+        Ok(Value::new_array(start..end, Type::Integer, false)?)
+    }
+
+    fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
+        let Type::Integer = self.start.get_type(context)? else {
+            return Err("Start of range must evaluate to integer".into());
+        };
+        let Type::Integer = self.end.get_type(context)? else {
+            return Err("End of range must evaluate to integer".into());
+        };
+
+        Ok(Type::Array(Box::new(Type::Integer), false))
+    }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        self.start.walk(visitor);
+        self.end.walk(visitor);
+    }
+}
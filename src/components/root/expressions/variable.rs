@@ -1,6 +1,7 @@
 use serde::Serialize;
 
 use crate::{
+    ast::Visitor,
     components::{
         builtins::{get_builtin_instance_type, get_builtin_type},
         root::identifier::Identifier,
@@ -13,7 +14,11 @@ use crate::{
     Executor,
 };
 
-use super::{Expression, ExpressionComponent};
+use super::{BaseExpression, Expression, ExpressionComponent, IntegerLiteral};
+
+/// The largest tuple to_tuple can create, the type of a tuple holds the type of every item so
+/// this stops a script from using up all the memory while being type checked.
+const MAX_TUPLE_LENGTH: usize = 1024;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct VariableExpression {
@@ -42,6 +47,30 @@ impl VariableExpression {
                 .collect::<Result<Vec<_>, PostProcessError>>()?;
 
             if let Some(instance_type) = instance_type {
+                // The number of items in a tuple needs to be known when type checking, so the
+                // length given to to_tuple must be a literal
+                if let ("to_tuple", Type::Array(inner_type, _), [Type::Integer]) = (
+                    self.name.value.as_str(),
+                    &instance_type,
+                    argument_types.as_slice(),
+                ) {
+                    let Some(length) = self.get_literal_argument(0) else {
+                        return Err(
+                            "The length given to to_tuple must be an integer literal".into()
+                        );
+                    };
+                    let length = usize::try_from(length)
+                        .ok()
+                        .filter(|length| *length <= MAX_TUPLE_LENGTH)
+                        .ok_or_else(|| -> PostProcessError {
+                            format!(
+                                "The length given to to_tuple must be between 0 and {MAX_TUPLE_LENGTH}"
+                            )
+                            .into()
+                        })?;
+                    return Ok(Type::Tuple(vec![*inner_type.clone(); length]));
+                }
+
                 if let Some(return_type) = get_builtin_instance_type(
                     &self.name.value,
                     instance_type,
@@ -53,7 +82,29 @@ impl VariableExpression {
                 return Err("Instance function not found".into());
             }
 
-            let Some(function) = context.functions.remove(&self.name.value) else {
+            // Variables holding a function can be called in the same way as the function itself
+            if let Some(Type::Function(function_arguments, return_type)) =
+                context.find_variable(&self.name.value)
+            {
+                if function_arguments != argument_types {
+                    return Err("Arguments are not correct".into());
+                }
+
+                return Ok(*return_type);
+            }
+
+            let Some((arguments, return_type)) = context.functions.get(&self.name.value).cloned()
+            else {
+                if let Some((arguments, return_type)) =
+                    context.custom_builtins.get(&self.name.value)
+                {
+                    if *arguments != argument_types {
+                        return Err("Arguments are not correct".into());
+                    }
+
+                    return Ok(return_type.clone());
+                }
+
                 if let Some(return_type) =
                     get_builtin_type(&self.name.value, argument_types.as_slice())
                 {
@@ -63,24 +114,39 @@ impl VariableExpression {
                 return Err("Function not found".into());
             };
 
-            if function.0 != argument_types {
+            if arguments != argument_types {
                 return Err("Arguments are not correct".into());
             }
 
-            let return_type = function.1.clone();
-
-            context.functions.insert(self.name.value.clone(), function);
-
             return Ok(return_type);
         } else {
             let variable_name = self.name.value.as_str();
             let Some(value_type) = context.find_variable(variable_name) else {
+                if let Some((arguments, return_type)) = context.functions.get(variable_name) {
+                    return Ok(Type::Function(
+                        arguments.clone(),
+                        return_type.clone().into(),
+                    ));
+                }
+
                 return Err(format!("Variable '{variable_name}' has not been declared").into());
             };
 
             return Ok(value_type);
         }
     }
+
+    fn get_literal_argument(&self, index: usize) -> Option<i32> {
+        let Expression {
+            first: BaseExpression::IntegerLiteral(IntegerLiteral { value }),
+            operations,
+        } = self.arguments.as_ref()?.get(index)?
+        else {
+            return None;
+        };
+
+        operations.is_empty().then_some(*value)
+    }
 }
 
 impl ExpressionComponent for VariableExpression {
@@ -133,6 +199,12 @@ impl ExpressionComponent for VariableExpression {
     fn get_type(&self, context: &mut PostProcessContext) -> Result<Type, PostProcessError> {
         self.get_type_impl(None, context)
     }
+
+    fn walk(&self, visitor: &mut dyn Visitor) {
+        for argument in self.arguments.iter().flatten() {
+            argument.walk(visitor);
+        }
+    }
 }
 
 impl VariableExpression {
@@ -148,9 +220,12 @@ impl VariableExpression {
                 .map(|x| x.evaluate(stack, executor))
                 .collect::<Result<Vec<_>, _>>()?;
 
-            stack
-                .execute_function(&self.name.value, instance, arguments, executor)?
-                .into()
+            let function_name = match (&instance, stack.resolve_variable(&self.name.value)) {
+                (None, Ok(Value::Function(function_name, _, _))) => function_name,
+                _ => self.name.value.clone(),
+            };
+
+            stack.execute_function(&function_name, instance, arguments, executor)?
         } else if instance.is_none() {
             stack.resolve_variable(&self.name.value)?.into()
         } else {
@@ -1,6 +1,7 @@
 use serde::Serialize;
 
 use crate::{
+    ast::Visitor,
     components::values::Type,
     constants::FUNC,
     lexer::{Token, TokenValue},
@@ -19,6 +20,11 @@ pub struct Function {
 }
 
 impl Function {
+    pub(crate) fn walk(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_function(self);
+        self.code.walk(visitor);
+    }
+
     pub(super) fn try_parse<'a, I: Iterator<Item = &'a Token<'a>>>(
         tokens: &mut Backtrackable<I>,
     ) -> Result<Option<Function>, ParserError> {
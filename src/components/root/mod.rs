@@ -1,15 +1,23 @@
 use serde::Serialize;
-use statement::Statement;
 
-use crate::{errors::PostProcessError, lexer::Token, utils::iterators::Backtrackable, Executor};
+use crate::{
+    ast::Visitor, errors::PostProcessError, lexer::Token, utils::iterators::Backtrackable, Executor,
+};
 
 use super::{
     errors::{ExecutionError, ParserError},
     stack::Stack,
-    ControlFlowOptions, EvaluationException, PostProcessContext, Scope, ScopeType, Tokens,
+    values::Type,
+    ControlFlowOptions, EvaluationException, PostProcessContext, ScopeType, Tokens,
 };
 
+pub use block::Block;
+pub use expressions::*;
 pub use function::Function;
+pub use identifier::Identifier;
+pub use operator::Operator;
+pub use statement::{Assignment, Statement};
+pub use type_definition::TypeDefinition;
 
 mod block;
 mod expressions;
@@ -52,22 +60,55 @@ impl Root {
     }
 
     pub fn post_process(&self, context: &mut PostProcessContext) -> Result<(), PostProcessError> {
-        // Add new variable scope for the root block
-        context.scopes.push(Scope::new(ScopeType::Root));
+        self.declare_functions(context);
 
         for function in &self.functions {
-            function.code.post_process_with_initializer(
-                |context| {
-                    for (name, value_type) in &function.arguments {
-                        context.declare_variable(name.value.clone(), value_type.value.clone())
-                    }
+            Self::post_process_function(function, context)?;
+        }
+
+        for statement in &self.statements {
+            statement.post_process(context)?;
+        }
+
+        Ok(())
+    }
+
+    /// Like `post_process` but carries on after an error so that every function and top level
+    /// statement gets checked. An error in one statement can cause follow on errors in later
+    /// statements that depend on it (e.g. if a variable failed to be declared).
+    pub fn post_process_all(&self, context: &mut PostProcessContext) -> Vec<PostProcessError> {
+        self.declare_functions(context);
+
+        let mut errors = Vec::new();
+        let mut collect_error = |result: Result<(), PostProcessError>,
+                                 context: &mut PostProcessContext,
+                                 depth: usize| {
+            if let Err(err) = result {
+                errors.push(err);
+                // Scopes aren't popped when returning early from an error
+                context.scopes.truncate(depth);
+            }
+        };
+
+        for function in &self.functions {
+            let depth = context.scopes.len();
+            let result = Self::post_process_function(function, context);
+            collect_error(result, context, depth);
+        }
 
-                    Ok(())
-                },
-                ScopeType::Function(function.return_type.value.clone()),
-                context,
-            )?;
+        for statement in &self.statements {
+            let depth = context.scopes.len();
+            let result = statement.post_process(context);
+            collect_error(result, context, depth);
+        }
+
+        errors
+    }
 
+    /// Declare every function before checking any of their bodies, so functions can call
+    /// themselves or each other regardless of the order they are defined in.
+    fn declare_functions(&self, context: &mut PostProcessContext) {
+        for function in &self.functions {
             let arguments = function
                 .arguments
                 .iter()
@@ -78,16 +119,50 @@ impl Root {
                 (arguments, function.return_type.value.clone()),
             );
         }
+    }
 
-        for statement in &self.statements {
-            statement.post_process(context)?;
+    fn post_process_function(
+        function: &Function,
+        context: &mut PostProcessContext,
+    ) -> Result<(), PostProcessError> {
+        let return_type = &function.return_type.value;
+        let result_type = function.code.post_process_with_initializer(
+            |context| {
+                for (name, value_type) in &function.arguments {
+                    context.declare_variable(name.value.clone(), value_type.value.clone())
+                }
+
+                Ok(())
+            },
+            ScopeType::Function(return_type.clone()),
+            context,
+        )?;
+
+        // A trailing expression is the return value, unless the function returns void in which
+        // case its value is ignored
+        if function.code.result.is_some()
+            && *return_type != Type::Void
+            && !result_type.is_assignable_to(return_type)
+        {
+            return Err(format!(
+                "Function {} has a declared return type of {return_type}, but its body results in a type of {result_type}",
+                function.name.value
+            )
+            .into());
         }
 
-        context.scopes.pop();
-
         Ok(())
     }
 
+    pub(crate) fn walk(&self, visitor: &mut dyn Visitor) {
+        for function in &self.functions {
+            function.walk(visitor);
+        }
+        for statement in &self.statements {
+            statement.walk(visitor);
+        }
+    }
+
     pub fn execute<E: Executor>(
         &self,
         stack: &mut Stack,
@@ -97,7 +172,6 @@ impl Root {
             stack.declare_function(&function.name.value, function.clone())?;
         }
 
-        stack.push_scope();
         let mut exit_code = 0;
         for statement in &self.statements {
             if let Err(exception) = statement.execute(stack, executor) {
@@ -109,17 +183,16 @@ impl Root {
                     EvaluationException::ControlFlow(ControlFlowOptions::Return(_)) => {
                         return Err("Return must be used in a function block".into())
                     }
-                    EvaluationException::ControlFlow(ControlFlowOptions::Break()) => {
+                    EvaluationException::ControlFlow(ControlFlowOptions::Break(_)) => {
                         return Err("Break must be used in a loop block".into())
                     }
-                    EvaluationException::ControlFlow(ControlFlowOptions::Continue()) => {
+                    EvaluationException::ControlFlow(ControlFlowOptions::Continue(_)) => {
                         return Err("Continue must be used in a loop block".into())
                     }
                     EvaluationException::Error(err) => return Err(err),
                 };
             }
         }
-        stack.pop_scope();
 
         return Ok(exit_code);
     }
@@ -1,7 +1,8 @@
 use serde::Serialize;
 
 use crate::{
-    components::values::{Type, Value},
+    components::values::{Float, Type, Value},
+    constants::{CAP, MERGE},
     lexer::{Token, TokenValue},
     ParserError,
 };
@@ -23,6 +24,11 @@ pub enum Operator {
     LessThanOrEqual,
     And,
     Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
 }
 
 impl Operator {
@@ -34,13 +40,21 @@ impl Operator {
                 {
                     'block: {
                         let checkpoint = tokens.checkpoint();
+                        let mut matched = Vec::new();
                         $(
-                            let Some(TokenValue::$token()) = tokens.next_value() else {
+                            let Some(token @ Token { value: TokenValue::$token(), .. }) = tokens.next() else {
                                 tokens.backtrack(checkpoint);
                                 break 'block
                             };
+                            matched.push(token);
                         )*
 
+                        // Operators made of multiple tokens can't have whitespace between them (e.g. `> =` isn't `>=`)
+                        if matched.windows(2).any(|pair| pair[0].end != pair[1].start) {
+                            tokens.backtrack(checkpoint);
+                            break 'block
+                        }
+
                         {
                             use Operator::*;
                             return Ok(Some($result));
@@ -50,19 +64,43 @@ impl Operator {
             };
         }
 
+        // Operators that start with the same tokens as another operator must be matched first
         match_tokens!([Plus] => Addition);
         match_tokens!([Dash] => Subtraction);
         match_tokens!([Star] => Multiplication);
         match_tokens!([ForwardSlash] => Division);
         match_tokens!([Percent] => Remainder);
-        match_tokens!([LeftAngle] => LessThan);
         match_tokens!([LeftAngle, Equals] => LessThanOrEqual);
-        match_tokens!([RightAngle] => GreaterThan);
+        match_tokens!([LeftAngle, LeftAngle] => ShiftLeft);
+        match_tokens!([LeftAngle] => LessThan);
         match_tokens!([RightAngle, Equals] => GreaterThanOrEqual);
+        match_tokens!([RightAngle, RightAngle] => ShiftRight);
+        match_tokens!([RightAngle] => GreaterThan);
         match_tokens!([Equals, Equals] => Equal);
         match_tokens!([Bang, Equals] => NotEqual);
         match_tokens!([And, And] => And);
+        match_tokens!([And] => BitAnd);
         match_tokens!([Pipe, Pipe] => Or);
+        match_tokens!([Caret] => BitXor);
+
+        // A single | followed by cap or merge stderr is the start of the options for a command in
+        // a pipeline. merge is not a keyword, so it can still be used as a name (e.g. `a | merge`)
+        let checkpoint = tokens.checkpoint();
+        if let Some(TokenValue::Pipe()) = tokens.next_value() {
+            let operand = tokens.checkpoint();
+            let is_command_options = match tokens.next_value() {
+                Some(TokenValue::Keyword(CAP)) => true,
+                Some(TokenValue::Identifier(MERGE)) => {
+                    matches!(tokens.next_value(), Some(TokenValue::Identifier("stderr")))
+                }
+                _ => false,
+            };
+            if !is_command_options {
+                tokens.backtrack(operand);
+                return Ok(Some(Operator::BitOr));
+            }
+        }
+        tokens.backtrack(checkpoint);
 
         return Ok(None);
     }
@@ -79,6 +117,9 @@ impl Operator {
         return_true_if_match!(Self::Multiplication);
         return_true_if_match!(Self::Addition | Self::Subtraction);
         return_true_if_match!(Self::And | Self::Or);
+        return_true_if_match!(Self::BitAnd);
+        return_true_if_match!(Self::BitOr);
+        return_true_if_match!(Self::BitXor);
 
         return false;
     }
@@ -97,6 +138,22 @@ macro_rules! impl_operator {
                     (Operator::Equal, left, right) => Ok((left == right).into()),
                     (Operator::NotEqual, left, right) => Ok((left != right).into()),
 
+                    // Shifts can fail depending on the amount being shifted by so are also implemented manually,
+                    // right shifts are arithmetic so negative numbers keep their sign (e.g. -8 >> 1 == -4).
+                    (Operator::ShiftLeft, Value::Integer(left), Value::Integer(right)) => {
+                        Ok(left.checked_shl(shift_amount(right)?).unwrap_or_default().into())
+                    }
+                    (Operator::ShiftRight, Value::Integer(left), Value::Integer(right)) => {
+                        Ok(left.checked_shr(shift_amount(right)?).unwrap_or(if left < 0 { -1 } else { 0 }).into())
+                    }
+
+                    // Float arithmetic is also implemented manually as it fails if the result isn't finite
+                    (Operator::Addition, Value::Float(left), Value::Float(right)) => float_result(left.value() + right.value()),
+                    (Operator::Subtraction, Value::Float(left), Value::Float(right)) => float_result(left.value() - right.value()),
+                    (Operator::Multiplication, Value::Float(left), Value::Float(right)) => float_result(left.value() * right.value()),
+                    (Operator::Division, Value::Float(left), Value::Float(right)) => float_result(left.value() / right.value()),
+                    (Operator::Remainder, Value::Float(left), Value::Float(right)) => float_result(left.value() % right.value()),
+
                     (operator, left, right) => {
                         Err(format!("Invalid operator expression {left:?} {operator:?} {right:?}.").into())
                     }
@@ -110,6 +167,17 @@ macro_rules! impl_operator {
                     )*
                     (Operator::Equal, _, _) => Ok(Type::Boolean),
                     (Operator::NotEqual, _, _) => Ok(Type::Boolean),
+                    (Operator::ShiftLeft, Type::Integer, Type::Integer) => Ok(Type::Integer),
+                    (Operator::ShiftRight, Type::Integer, Type::Integer) => Ok(Type::Integer),
+                    (
+                        Operator::Addition
+                        | Operator::Subtraction
+                        | Operator::Multiplication
+                        | Operator::Division
+                        | Operator::Remainder,
+                        Type::Float,
+                        Type::Float,
+                    ) => Ok(Type::Float),
                     (operator, left, right) => {
                         Err(format!("Invalid operator expression {left:?} {operator:?} {right:?}.").into())
                     }
@@ -150,10 +218,47 @@ impl_operator![
     GreaterThanOrEqual(Integer, Integer) -> Boolean{
         |left, right| left >= right
     },
+    LessThan(Float, Float) -> Boolean{
+        |left: Float, right: Float| left.value() < right.value()
+    },
+    GreaterThan(Float, Float) -> Boolean{
+        |left: Float, right: Float| left.value() > right.value()
+    },
+    LessThanOrEqual(Float, Float) -> Boolean{
+        |left: Float, right: Float| left.value() <= right.value()
+    },
+    GreaterThanOrEqual(Float, Float) -> Boolean{
+        |left: Float, right: Float| left.value() >= right.value()
+    },
     And(Boolean, Boolean) -> Boolean{
         |left, right| left && right
     },
     Or(Boolean, Boolean) -> Boolean{
         |left, right| left || right
+    },
+    BitAnd(Integer, Integer) -> Integer {
+        |left, right| left & right
+    },
+    BitOr(Integer, Integer) -> Integer {
+        |left, right| left | right
+    },
+    BitXor(Integer, Integer) -> Integer {
+        |left, right| left ^ right
     }
 ];
+
+/// Shifting by 32 or more bits is allowed and shifts every bit out, but shifting by a negative
+/// amount doesn't have an obvious meaning so is an error.
+fn shift_amount(value: i32) -> Result<u32, ExecutionError> {
+    u32::try_from(value).map_err(|_| format!("Can not shift by a negative amount ({value})").into())
+}
+
+fn float_result(value: f64) -> Result<Value, ExecutionError> {
+    let Some(result) = Float::new(value) else {
+        return Err(
+            format!("Float operation resulted in {value}, which is not a finite number").into(),
+        );
+    };
+
+    Ok(result.into())
+}
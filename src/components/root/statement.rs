@@ -1,12 +1,14 @@
 use serde::Serialize;
 
 use crate::{
+    ast::Visitor,
     components::{
+        builtins,
         stack::Stack,
         values::{Type, Value},
-        ControlFlowOptions, EvaluationResult, PostProcessContext, ScopeType,
+        ControlFlowOptions, EvaluationException, EvaluationResult, PostProcessContext, ScopeType,
     },
-    constants::{BREAK, CONTINUE, EXIT, MUT, RETURN, VAR},
+    constants::{BREAK, CATCH, CONTINUE, EXIT, MUT, RETURN, TRY, UNDERSCORE, VAR},
     errors::PostProcessError,
     lexer::{Token, TokenValue},
     utils::iterators::Backtrackable,
@@ -14,7 +16,8 @@ use crate::{
 };
 
 use super::{
-    expressions::Expression, identifier::Identifier, type_definition::TypeDefinition, Tokens,
+    block::Block, expressions::Expression, identifier::Identifier, operator::Operator,
+    type_definition::TypeDefinition, Tokens,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -22,11 +25,13 @@ pub enum Statement {
     Declaration(Identifier, TypeDefinition),
     DeclarationAssignment(bool, Assignment, Expression),
     Assignment(Assignment, Expression),
+    Append(Identifier, Expression),
     Expression(Expression),
     Exit(Expression),
-    Return(Expression),
-    Break(),
-    Continue(),
+    Return(Option<Expression>),
+    Break(Option<Identifier>),
+    Continue(Option<Identifier>),
+    TryCatch(Block, Identifier, Block),
 }
 
 impl Statement {
@@ -52,37 +57,85 @@ impl Statement {
                     Assignment::Simple(name) => {
                         context.declare_variable(name.value.clone(), variable_type)
                     }
-                    Assignment::Tuple(_) => todo!(),
+                    Assignment::Compound(_, _) => {
+                        return Err(
+                            "Compound assignments can not be used to declare a variable".into()
+                        )
+                    }
+                    Assignment::Tuple(names) => {
+                        let element_types = Self::get_tuple_types(names, variable_type)?;
+                        for (name, element_type) in names.iter().zip(element_types) {
+                            context.declare_variable(name.value.clone(), element_type);
+                        }
+                    }
                 }
             }
             Statement::Assignment(assignment, value) => match assignment {
                 Assignment::Simple(name) => {
+                    let value_type = value.get_type(context)?;
+                    Self::check_assignable(name, value_type, context)?;
+                }
+                Assignment::Compound(name, operator) => {
                     let name = name.value.clone();
                     let variable_type = context.find_variable(name.as_str()).ok_or::<PostProcessError>(
                         format!("Unable to assign to variable '{name}' has it has not been declared yet").into()
                     )?;
                     let value_type = value.get_type(context)?;
-                    if !value_type.is_assignable_to(&variable_type) {
+                    let result_type = operator.get_type(variable_type.clone(), value_type)?;
+                    if !result_type.is_assignable_to(&variable_type) {
                         return Err(format!(
-                            "Unable to assign a value of type '{value_type}' to a variable of type '{variable_type}'",
+                            "Unable to assign a value of type '{result_type}' to a variable of type '{variable_type}'",
                         ).into());
                     }
                 }
-                Assignment::Tuple(_) => todo!(),
+                Assignment::Tuple(names) => {
+                    let value_type = value.get_type(context)?;
+                    for (name, element_type) in
+                        names.iter().zip(Self::get_tuple_types(names, value_type)?)
+                    {
+                        Self::check_assignable(name, element_type, context)?;
+                    }
+                }
             },
+            Statement::Append(name, value) => {
+                let name = name.value.as_str();
+                let variable_type = context.find_variable(name).ok_or::<PostProcessError>(
+                    format!(
+                        "Unable to append to variable '{name}' as it has not been declared yet"
+                    )
+                    .into(),
+                )?;
+                let value_type = value.get_type(context)?;
+                match &variable_type {
+                    Type::String => {
+                        if value_type != Type::String {
+                            return Err(format!(
+                                "Unable to append a value of type '{value_type}' to a variable of type '{variable_type}'"
+                            ).into());
+                        }
+                    }
+                    Type::Array(inner_type, true) => {
+                        if value_type != **inner_type {
+                            return Err(format!(
+                                "Unable to append a value of type '{value_type}' to a variable of type '{variable_type}'"
+                            ).into());
+                        }
+                    }
+                    _ => {
+                        return Err(format!(
+                            "Can only append to variables of type string or mutable array, but '{name}' has type '{variable_type}'"
+                        ).into());
+                    }
+                }
+            }
             Statement::Expression(value) => {
-                value.get_type(context)?;
+                value.get_statement_type(context)?;
             }
+            // Exit can be used anywhere, including inside functions, as it stops the whole script
             Statement::Exit(value) => {
                 let Type::Integer = value.get_type(context)? else {
                     return Err("Value provided to an exit statement must be an integer".into());
                 };
-
-                // Currently everything is inside a root scope, but idk maybe at some point it wont be.
-                dbg!(&context);
-                if !context.has_parent_scope(&ScopeType::Root) {
-                    return Err("Exit statement can only be used from inside the root scope".into());
-                }
             }
             Statement::Return(value) => {
                 let Some(scope) = context.get_matching_parent_scope(|scope_type| {
@@ -96,30 +149,107 @@ impl Statement {
                 };
 
                 let declared_return_type = declared_return_type.clone();
-                let actual_return_type = value.get_type(context)?;
+                let actual_return_type = match value {
+                    Some(value) => value.get_type(context)?,
+                    None => Type::Void,
+                };
                 if actual_return_type != declared_return_type {
                     return Err(format!("Function has a declared return type of {declared_return_type}, but return statement got a type of {actual_return_type}").into());
                 }
             }
-            Statement::Break() => {
-                if !context.has_parent_scope(&ScopeType::Looped) {
+            Statement::Break(label) => {
+                let label = label.as_ref().map(|label| label.value.as_str());
+                if !context.has_loop_scope(label) {
+                    if let Some(label) = label {
+                        return Err(format!(
+                            "Break statement must be inside a loop with the label {label}"
+                        )
+                        .into());
+                    }
                     return Err(
                         "Break statement can only be used from inside a looped block".into(),
                     );
                 }
             }
-            Statement::Continue() => {
-                if !context.has_parent_scope(&ScopeType::Looped) {
+            Statement::Continue(label) => {
+                let label = label.as_ref().map(|label| label.value.as_str());
+                if !context.has_loop_scope(label) {
+                    if let Some(label) = label {
+                        return Err(format!(
+                            "Continue statement must be inside a loop with the label {label}"
+                        )
+                        .into());
+                    }
                     return Err(
                         "Continue statement can only be used from inside a looped block".into(),
                     );
                 }
             }
+            Statement::TryCatch(body, error_name, handler) => {
+                body.post_process(context)?;
+                handler.post_process_with_initializer(
+                    |context| {
+                        context.declare_variable(error_name.value.clone(), Type::String);
+                        Ok(())
+                    },
+                    ScopeType::Block,
+                    context,
+                )?;
+            }
         }
 
         Ok(())
     }
 
+    fn check_assignable(
+        name: &Identifier,
+        value_type: Type,
+        context: &mut PostProcessContext,
+    ) -> Result<(), PostProcessError> {
+        let name = name.value.as_str();
+        // Assigning to _ throws the value away, so there is no variable to check
+        if name == UNDERSCORE {
+            return Ok(());
+        }
+
+        let variable_type = context.find_variable(name).ok_or::<PostProcessError>(
+            format!("Unable to assign to variable '{name}' has it has not been declared yet")
+                .into(),
+        )?;
+        if !value_type.is_assignable_to(&variable_type) {
+            return Err(format!(
+                "Unable to assign a value of type '{value_type}' to a variable of type '{variable_type}'",
+            ).into());
+        }
+
+        Ok(())
+    }
+
+    /// Tuple assignments must name every element of the tuple, so that values can't be silently
+    /// dropped.
+    fn get_tuple_types(
+        names: &[Identifier],
+        value_type: Type,
+    ) -> Result<Vec<Type>, PostProcessError> {
+        let Type::Tuple(element_types) = value_type else {
+            return Err(format!(
+                "Unable to use a tuple assignment with a value of type '{value_type}'"
+            )
+            .into());
+        };
+
+        if names.len() != element_types.len() {
+            return Err(format!(
+                "Tuple assignment has {} names, but the value has {} elements",
+                names.len(),
+                element_types.len()
+            )
+            .into());
+        }
+
+        Ok(element_types)
+    }
+
     pub fn execute<E: Executor>(
         &self,
         stack: &mut Stack,
@@ -136,6 +266,11 @@ impl Statement {
                     Assignment::Simple(identifier) => {
                         stack.assign_variable(&identifier.value, result)?;
                     }
+                    Assignment::Compound(identifier, operator) => {
+                        let current = stack.resolve_variable(&identifier.value)?;
+                        let result = operator.execute(current, result)?;
+                        stack.assign_variable(&identifier.value, result)?;
+                    }
                     Assignment::Tuple(identifiers) => {
                         let Value::Tuple(result) = result else {
                             return Err(
@@ -159,6 +294,11 @@ impl Statement {
                     Assignment::Simple(identifier) => {
                         stack.declare_variable_init(&identifier.value, result, *mutable)?;
                     }
+                    Assignment::Compound(_, _) => {
+                        return Err(
+                            "Compound assignments can not be used to declare a variable".into()
+                        );
+                    }
                     Assignment::Tuple(identifiers) => {
                         let Value::Tuple(result) = result else {
                             return Err(
@@ -171,16 +311,30 @@ impl Statement {
                         }
 
                         for (identifier, result) in identifiers.iter().zip(result) {
-                            stack.declare_variable_init(&identifier.value, result, true)?;
+                            stack.declare_variable_init(&identifier.value, result, *mutable)?;
                         }
                     }
                 }
             }
+            Statement::Append(identifier, expression) => {
+                let value = expression.evaluate(stack, executor)?;
+                let current = stack.resolve_variable(&identifier.value)?;
+                if let Value::Array(_, _, _) = current {
+                    // Arrays are appended to in place, so this behaves exactly like push
+                    builtins::call_builtin_instance("push", &current, &[value], stack, executor)?;
+                } else {
+                    let result = Operator::Addition.execute(current, value)?;
+                    stack.assign_variable(&identifier.value, result)?;
+                }
+            }
             Statement::Expression(expression) => {
                 expression.evaluate(stack, executor)?;
             }
             Statement::Return(expression) => {
-                let result = expression.evaluate(stack, executor)?;
+                let result = match expression {
+                    Some(expression) => expression.evaluate(stack, executor)?,
+                    None => Value::Void,
+                };
                 return Err(ControlFlowOptions::Return(result).into());
             }
             Statement::Exit(expression) => {
@@ -194,14 +348,59 @@ impl Statement {
 
                 return Err(ControlFlowOptions::Exit(exit_code).into());
             }
-            Statement::Break() => return Err(ControlFlowOptions::Break().into()),
-            Statement::Continue() => return Err(ControlFlowOptions::Continue().into()),
+            Statement::Break(label) => {
+                let label = label.as_ref().map(|label| label.value.clone());
+                return Err(ControlFlowOptions::Break(label).into());
+            }
+            Statement::Continue(label) => {
+                let label = label.as_ref().map(|label| label.value.clone());
+                return Err(ControlFlowOptions::Continue(label).into());
+            }
+            Statement::TryCatch(body, error_name, handler) => {
+                match body.execute(stack, executor) {
+                    // Only errors are caught, control flow like break or return passes through
+                    Err(EvaluationException::Error(err)) => {
+                        handler.execute_with_initializer(
+                            |stack| {
+                                stack.declare_variable_init(
+                                    &error_name.value,
+                                    err.message.into(),
+                                    false,
+                                )
+                            },
+                            stack,
+                            executor,
+                        )?;
+                    }
+                    result => {
+                        result?;
+                    }
+                }
+            }
         };
 
         return Ok(Value::Void);
     }
 
-    fn parse_content<'a, I: Iterator<Item = &'a Token<'a>>>(
+    pub(crate) fn walk(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_statement(self);
+        match self {
+            Statement::Declaration(_, _) | Statement::Break(_) | Statement::Continue(_) => {}
+            Statement::DeclarationAssignment(_, _, expression)
+            | Statement::Assignment(_, expression)
+            | Statement::Append(_, expression)
+            | Statement::Expression(expression)
+            | Statement::Exit(expression)
+            | Statement::Return(Some(expression)) => expression.walk(visitor),
+            Statement::Return(None) => {}
+            Statement::TryCatch(body, _, handler) => {
+                body.walk(visitor);
+                handler.walk(visitor);
+            }
+        }
+    }
+
+    pub(super) fn parse_content<'a, I: Iterator<Item = &'a Token<'a>>>(
         tokens: &mut Backtrackable<I>,
     ) -> Result<Statement, ParserError> {
         let next = tokens.peek_value();
@@ -249,19 +448,44 @@ impl Statement {
 
         if let Some(TokenValue::Keyword(RETURN)) = next {
             tokens.next();
-            return Ok(Statement::Return(Expression::parse(tokens)?));
+
+            // A bare return is allowed to exit early from functions that return void
+            if let Some(TokenValue::Semicolon()) = tokens.peek_value() {
+                return Ok(Statement::Return(None));
+            }
+
+            return Ok(Statement::Return(Some(Expression::parse(tokens)?)));
         };
 
         if let Some(TokenValue::Keyword(BREAK)) = next {
             tokens.next();
-            return Ok(Statement::Break());
+            return Ok(Statement::Break(Self::parse_label(tokens)));
         };
 
         if let Some(TokenValue::Keyword(CONTINUE)) = next {
             tokens.next();
-            return Ok(Statement::Continue());
+            return Ok(Statement::Continue(Self::parse_label(tokens)));
         };
 
+        if let Some(TokenValue::Keyword(TRY)) = next {
+            tokens.next();
+            let body = Block::parse(tokens)?;
+
+            let Some(TokenValue::Keyword(CATCH)) = tokens.next_value() else {
+                return Err("try block must be followed by catch".into());
+            };
+            let Some(TokenValue::Identifier(error_name)) = tokens.next_value() else {
+                return Err("catch must be followed by a name for the error".into());
+            };
+
+            let handler = Block::parse(tokens)?;
+            return Ok(Statement::TryCatch(body, (*error_name).into(), handler));
+        };
+
+        if let Some(identifier) = tokens.backtrack_if_none(Self::parse_append) {
+            return Ok(Statement::Append(identifier, Expression::parse(tokens)?));
+        }
+
         if let Some(assignment) = Assignment::try_parse(tokens) {
             return Ok(Statement::Assignment(
                 assignment,
@@ -273,11 +497,43 @@ impl Statement {
         let expression = Expression::parse(tokens)?;
         return Ok(Statement::Expression(expression));
     }
+
+    fn parse_append<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Option<Identifier> {
+        let Some(TokenValue::Identifier(identifier)) = tokens.next_value() else {
+            return None;
+        };
+
+        // Same as compound assignments, there can't be whitespace inside the ++= (e.g. `+ + =`)
+        let mut previous_end = None;
+        for expected in [TokenValue::Plus(), TokenValue::Plus(), TokenValue::Equals()] {
+            let token = tokens.next()?;
+            if token.value != expected || previous_end.is_some_and(|end| end != token.start) {
+                return None;
+            }
+            previous_end = Some(token.end);
+        }
+
+        Some((*identifier).into())
+    }
+
+    fn parse_label<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Option<Identifier> {
+        let Some(TokenValue::Identifier(label)) = tokens.peek_value() else {
+            return None;
+        };
+        tokens.next();
+
+        Some((*label).into())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum Assignment {
     Simple(Identifier),
+    Compound(Identifier, Operator),
     Tuple(Vec<Identifier>),
 }
 
@@ -293,11 +549,33 @@ impl Assignment {
     ) -> Option<Self> {
         let next = tokens.next_value();
         if let Some(TokenValue::Identifier(identifier)) = next {
-            return if let Some(TokenValue::Equals()) = tokens.next_value() {
-                Some(Assignment::Simple((*identifier).into()))
-            } else {
-                None
+            let operator_token = tokens.next()?;
+            let operator = match operator_token.value {
+                TokenValue::Equals() => return Some(Assignment::Simple((*identifier).into())),
+                TokenValue::Plus() => Operator::Addition,
+                TokenValue::Dash() => Operator::Subtraction,
+                TokenValue::Star() => Operator::Multiplication,
+                TokenValue::ForwardSlash() => Operator::Division,
+                TokenValue::Percent() => Operator::Remainder,
+                _ => return None,
             };
+
+            let Some(
+                equals @ Token {
+                    value: TokenValue::Equals(),
+                    ..
+                },
+            ) = tokens.next()
+            else {
+                return None;
+            };
+
+            // Same as multi-token operators, there can't be whitespace before the = (e.g. `+ =`)
+            if operator_token.end != equals.start {
+                return None;
+            }
+
+            return Some(Assignment::Compound((*identifier).into(), operator));
         };
 
         if let Some(TokenValue::LeftBracket()) = next {
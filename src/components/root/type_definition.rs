@@ -2,8 +2,8 @@ use serde::Serialize;
 
 use crate::{
     components::values::Type,
-    constants::MUT,
-    lexer::{Token, TokenValue},
+    constants::{FUNC, MUT},
+    lexer::{lex, Token, TokenValue},
     utils::iterators::Backtrackable,
     ParserError,
 };
@@ -22,6 +22,19 @@ impl TypeDefinition {
         Ok(Self::parse_impl(tokens)?.into())
     }
 
+    /// Parses a type written the same way as in a script, for type names that are only known
+    /// at runtime.
+    pub fn parse_name(name: &str) -> Option<Type> {
+        let tokens = lex(name).collect::<Result<Vec<_>, _>>().ok()?;
+        let tokens = &mut Backtrackable::new(tokens.iter());
+        let result = Self::parse_impl(tokens).ok()?;
+        if tokens.next().is_some() {
+            return None;
+        }
+
+        Some(result)
+    }
+
     fn parse_impl<'a, I: Iterator<Item = &'a Token<'a>>>(
         tokens: &mut Backtrackable<I>,
     ) -> Result<Type, ParserError> {
@@ -42,8 +55,18 @@ impl TypeDefinition {
             return Ok(Type::Array(inner_type.into(), mutable));
         }
 
+        if let Some(TokenValue::LeftCurly()) = tokens.peek_value() {
+            tokens.next();
+            let inner_type = Self::parse_impl(tokens)?;
+            let Some(TokenValue::RightCurly()) = tokens.peek_value() else {
+                return Err("Expected } after inner type in map type definition".into());
+            };
+            tokens.next();
+            return Ok(Type::Map(inner_type.into(), mutable));
+        }
+
         if mutable {
-            return Err("Only array types can be mutable".into());
+            return Err("Only array and map types can be mutable".into());
         }
 
         return Ok(Self::parse_base_type(tokens)?);
@@ -61,34 +84,67 @@ impl TypeDefinition {
                 "void" => Type::Void,
                 "string" => Type::String,
                 "integer" => Type::Integer,
+                "float" => Type::Float,
+                "char" => Type::Char,
                 "boolean" => Type::Boolean,
                 "command" => Type::Command,
+                "file_handle" => Type::FileHandle,
+                "json" => Type::Json,
                 _ => return Err(format!("{type_name} is not a valid type name").into()),
             });
         }
 
         if let Some(TokenValue::LeftBracket()) = next {
+            return Ok(Type::Tuple(Self::parse_type_list(tokens)?));
+        }
+
+        if let Some(TokenValue::Keyword(FUNC)) = next {
             tokens.next();
-            let mut types = Vec::new();
-            loop {
-                types.push(Self::parse_impl(tokens)?);
-
-                let next = tokens.peek_value();
-                if let Some(TokenValue::RightBracket()) = next {
-                    tokens.next();
-                    break;
-                }
-                let Some(TokenValue::Comma()) = next else {
-                    return Err("Expected , after type definition".into());
-                };
+            let Some(TokenValue::LeftBracket()) = tokens.peek_value() else {
+                return Err("Expected ( after func in function type definition".into());
+            };
+            let arguments = Self::parse_type_list(tokens)?;
+
+            // Same as function declarations, leaving out the return type means it returns void
+            let return_type = if let Some(TokenValue::Colon()) = tokens.peek_value() {
                 tokens.next();
-            }
+                Self::parse_impl(tokens)?
+            } else {
+                Type::Void
+            };
 
-            return Ok(Type::Tuple(types));
+            return Ok(Type::Function(arguments, return_type.into()));
         }
 
         return Err("Unable to parse type definition".into());
     }
+
+    fn parse_type_list<'a, I: Iterator<Item = &'a Token<'a>>>(
+        tokens: &mut Backtrackable<I>,
+    ) -> Result<Vec<Type>, ParserError> {
+        tokens.next();
+        let mut types = Vec::new();
+        if let Some(TokenValue::RightBracket()) = tokens.peek_value() {
+            tokens.next();
+            return Ok(types);
+        }
+
+        loop {
+            types.push(Self::parse_impl(tokens)?);
+
+            let next = tokens.peek_value();
+            if let Some(TokenValue::RightBracket()) = next {
+                tokens.next();
+                break;
+            }
+            let Some(TokenValue::Comma()) = next else {
+                return Err("Expected , after type definition".into());
+            };
+            tokens.next();
+        }
+
+        Ok(types)
+    }
 }
 
 impl From<Type> for TypeDefinition {
@@ -96,3 +152,54 @@ impl From<Type> for TypeDefinition {
         Self { value }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::lex;
+
+    use super::*;
+
+    fn parse_type(value: &str) -> Type {
+        let tokens = lex(value).collect::<Result<Vec<_>, _>>().unwrap();
+        let tokens = &mut Backtrackable::new(tokens.iter());
+        let result = TypeDefinition::parse(tokens).unwrap();
+        assert_eq!(tokens.next(), None, "Expected {value} to be fully parsed");
+
+        result.value
+    }
+
+    #[test]
+    fn should_parse_every_displayed_type() {
+        let types = vec![
+            Type::Void,
+            Type::String,
+            Type::Integer,
+            Type::Float,
+            Type::Char,
+            Type::Boolean,
+            Type::Command,
+            Type::FileHandle,
+            Type::Json,
+            Type::Array(Type::String.into(), false),
+            Type::Array(Type::Array(Type::Integer.into(), true).into(), true),
+            Type::Map(Type::Boolean.into(), false),
+            Type::Map(Type::Array(Type::String.into(), false).into(), true),
+            Type::Tuple(vec![]),
+            Type::Tuple(vec![Type::Integer]),
+            Type::Tuple(vec![
+                Type::Integer,
+                Type::Tuple(vec![Type::String, Type::FileHandle]),
+                Type::Array(Type::Command.into(), true),
+            ]),
+            Type::Function(vec![], Type::Void.into()),
+            Type::Function(
+                vec![Type::Integer, Type::Array(Type::String.into(), false)],
+                Type::Function(vec![Type::String], Type::Boolean.into()).into(),
+            ),
+        ];
+
+        for value in types {
+            assert_eq!(parse_type(&format!("{value}")), value);
+        }
+    }
+}
@@ -12,17 +12,29 @@ use super::{
     values::{Type, Value},
 };
 
+/// A function provided by the program embedding nash, which can be called from scripts like any
+/// other builtin.
+pub type CustomBuiltin = Box<dyn Fn(&[Value]) -> Result<Value, ExecutionError>>;
+
 pub struct Stack {
     functions: HashMap<String, Function>,
+    custom_builtins: HashMap<String, (Type, CustomBuiltin)>,
     scopes: Vec<Scope>,
     call_stack: Vec<String>,
 }
 
+/// A copy of the functions and variables declared on a stack, see `Stack::declarations`.
+pub struct Declarations {
+    functions: HashMap<String, Function>,
+    scopes: Vec<Scope>,
+}
+
 impl Stack {
     pub fn new() -> Self {
         Self {
             functions: HashMap::new(),
-            scopes: Vec::new(),
+            custom_builtins: HashMap::new(),
+            scopes: vec![Scope::new()],
             call_stack: Vec::new(),
         }
     }
@@ -31,6 +43,38 @@ impl Stack {
         &self.call_stack
     }
 
+    pub fn clear_call_stack(&mut self) {
+        self.call_stack.clear();
+    }
+
+    /// Copies everything that has been declared, so it can be put back with
+    /// `restore_declarations` if a script fails part way through.
+    pub fn declarations(&self) -> Declarations {
+        Declarations {
+            functions: self.functions.clone(),
+            scopes: self.scopes.clone(),
+        }
+    }
+
+    pub fn restore_declarations(&mut self, declarations: Declarations) {
+        self.functions = declarations.functions;
+        self.scopes = declarations.scopes;
+    }
+
+    pub fn register_builtin(&mut self, name: &str, return_type: Type, function: CustomBuiltin) {
+        self.custom_builtins
+            .insert(name.to_owned(), (return_type, function));
+    }
+
+    /// Drops every declared variable and function, leaving the stack as if it was just created.
+    /// Values can't contain themselves (an array can only hold values of its element type) so
+    /// there are no Rc cycles, and dropping the scopes frees everything they reference.
+    pub fn clear_user_scopes(&mut self) {
+        self.functions.clear();
+        self.scopes = vec![Scope::new()];
+        self.call_stack.clear();
+    }
+
     pub fn push_scope(&mut self) {
         self.scopes.push(Scope::new());
     }
@@ -110,7 +154,7 @@ impl Stack {
     fn set_variable(variable: &mut Variable, value: Value) -> Result<(), ExecutionError> {
         let variable_type = &variable.value_type;
         let value_type = value.get_type();
-        if !variable_type.is_assignable_to(&value_type) {
+        if !value_type.is_assignable_to(variable_type) {
             return Err(format!(
                 "Can not assign a value of type {value_type} to a variable of type {variable_type}"
             )
@@ -140,6 +184,21 @@ impl Stack {
     }
 
     pub fn resolve_variable(&self, variable_name: &str) -> Result<Value, ExecutionError> {
+        // Functions can be referenced by name, unless there is a variable with the same name
+        if self.get_variable(variable_name).is_none() {
+            if let Some(function) = self.functions.get(variable_name) {
+                return Ok(Value::Function(
+                    variable_name.to_owned(),
+                    function
+                        .arguments
+                        .iter()
+                        .map(|(_, argument_type)| argument_type.value.clone())
+                        .collect(),
+                    function.return_type.value.clone(),
+                ));
+            }
+        }
+
         Ok(self
             .get_variable(variable_name)
             .ok_or::<ExecutionError>(
@@ -168,16 +227,47 @@ impl Stack {
         }
 
         self.call_stack.push(function_name.to_owned());
-        let result = if let Some(instance) = instance {
-            builtins::call_builtin_instance(function_name, &instance, &arguments, executor)?
+        let mut result = if let Some(instance) = instance {
+            builtins::call_builtin_instance(function_name, &instance, &arguments, self, executor)
         } else if let Some(function) = self.functions.get(function_name) {
-            self.call_function(function.clone(), arguments, executor)?
+            // The function is cloned rather than taken out of the map, so it can still be
+            // resolved if it calls itself (recursion is bounded by the call stack depth above)
+            self.call_function(function.clone(), arguments, executor)
+        } else if let Some(builtin) = self.custom_builtins.get(function_name) {
+            Self::call_custom_builtin(function_name, builtin, &arguments).map_err(Into::into)
         } else {
-            builtins::call_builtin(function_name, &arguments, executor)?
+            builtins::call_builtin(function_name, &arguments, executor).map_err(Into::into)
         };
 
+        // The innermost call sees the error first, so it records the stack as it was at the
+        // point the error happened before we start unwinding
+        if let Err(EvaluationException::Error(err)) = &mut result {
+            if err.call_stack.is_none() {
+                err.set_call_stack(self.call_stack.clone());
+            }
+        }
+
         self.call_stack.pop();
-        return Ok(result);
+        result
+    }
+
+    fn call_custom_builtin(
+        function_name: &str,
+        (return_type, function): &(Type, CustomBuiltin),
+        arguments: &[Value],
+    ) -> Result<Value, ExecutionError> {
+        let result = function(arguments)?;
+
+        // Unlike nash functions there is nothing checking what custom builtins return
+        let value_type = result.get_type();
+        if !value_type.is_assignable_to(return_type) {
+            return Err(format!(
+                "Builtin {function_name} should return type {return_type} but got value with type {value_type}"
+            )
+            .into());
+        }
+
+        Ok(result)
     }
 
     fn call_function<E: Executor>(
@@ -205,7 +295,7 @@ impl Stack {
                 for (value, (name, argument_type)) in arguments.into_iter().zip(function.arguments)
                 {
                     let value_type = value.get_type();
-                    if argument_type.value != value_type {
+                    if !value_type.is_assignable_to(&argument_type.value) {
                         return Err(format!(
                             "Argument {} has type {} but got value with type {}",
                             name.value, argument_type.value, value_type
@@ -222,23 +312,26 @@ impl Stack {
         );
 
         self.scopes = outer_scope;
-        let result = if let Err(exception) = result {
-            match exception {
+        let result = match result {
+            // The value of a trailing expression is only returned when the function has a
+            // return type, so void functions can still end with a call that returns something
+            Ok(value) if function.return_type.value != Type::Void => value,
+            Ok(_) => Value::Void,
+            Err(exception) => match exception {
                 EvaluationException::ControlFlow(ControlFlowOptions::Return(value)) => value,
-                EvaluationException::ControlFlow(ControlFlowOptions::Break()) => {
+                EvaluationException::ControlFlow(ControlFlowOptions::Break(_)) => {
                     return Err("Break must be used in a loop block".into())
                 }
-                EvaluationException::ControlFlow(ControlFlowOptions::Continue()) => {
+                EvaluationException::ControlFlow(ControlFlowOptions::Continue(_)) => {
                     return Err("Continue must be used in a loop block".into())
                 }
+                // Exit carries on past the function so that it stops the whole script
                 err => return Err(err),
-            }
-        } else {
-            Value::Void
+            },
         };
 
         let value_type = result.get_type();
-        if value_type != function.return_type.value {
+        if !value_type.is_assignable_to(&function.return_type.value) {
             return Err(format!(
                 "Function {} should return type {} but got value with type {}",
                 function.name.value, function.return_type.value, value_type
@@ -268,6 +361,7 @@ impl Stack {
         None
     }
 }
+#[derive(Clone)]
 struct Scope {
     variables: HashMap<String, Variable>,
     hidden_variables: Vec<Variable>,
@@ -306,6 +400,7 @@ impl Scope {
     }
 }
 
+#[derive(Clone)]
 struct Variable {
     pub value: Option<Value>,
     pub value_type: Type,
@@ -1,20 +1,93 @@
-use std::{cell::RefCell, fmt::Display, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc};
 
 use serde::Serialize;
 
 use crate::{utils::formatting::fmt_collection, ExecutionError};
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Default)]
 pub enum Value {
     #[default]
     Void,
+    /// A json null, which unlike void can be stored in variables and collections of json.
+    Null,
     String(String),
     Integer(i32),
+    Float(Float),
+    Char(char),
     Boolean(bool),
     Command(String, Vec<String>),
     Array(Rc<RefCell<Vec<Value>>>, Type, bool),
+    Map(Rc<RefCell<HashMap<String, Value>>>, Type, bool),
     Tuple(Vec<Value>),
     FileHandle(String, FileMode),
+    Function(String, Vec<Type>, Type),
+}
+
+/// Arrays and maps are equal when they have the same contents and the same item type, whether or
+/// not they (or any collections nested inside them) are mutable. The item type is still compared
+/// even when there are no items, so an empty `[string]` is never equal to an empty `[integer]`.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Void, Value::Void) => true,
+            (Value::Null, Value::Null) => true,
+            (Value::String(left), Value::String(right)) => left == right,
+            (Value::Integer(left), Value::Integer(right)) => left == right,
+            (Value::Float(left), Value::Float(right)) => left == right,
+            (Value::Char(left), Value::Char(right)) => left == right,
+            (Value::Boolean(left), Value::Boolean(right)) => left == right,
+            (Value::Command(left, left_args), Value::Command(right, right_args)) => {
+                left == right && left_args == right_args
+            }
+            (Value::Array(left, left_type, _), Value::Array(right, right_type, _)) => {
+                left_type.eq_ignoring_mutability(right_type) && left == right
+            }
+            (Value::Map(left, left_type, _), Value::Map(right, right_type, _)) => {
+                left_type.eq_ignoring_mutability(right_type) && left == right
+            }
+            (Value::Tuple(left), Value::Tuple(right)) => left == right,
+            (Value::FileHandle(left, left_mode), Value::FileHandle(right, right_mode)) => {
+                left == right && left_mode == right_mode
+            }
+            (
+                Value::Function(left, left_args, left_return),
+                Value::Function(right, right_args, right_return),
+            ) => left == right && left_args == right_args && left_return == right_return,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+/// Floats are always finite, so unlike `f64` they can be compared for equality like every other
+/// value.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Float(f64);
+
+impl Eq for Float {}
+
+impl Float {
+    pub fn new(value: f64) -> Option<Self> {
+        value.is_finite().then_some(Self(value))
+    }
+
+    pub fn value(&self) -> f64 {
+        self.0
+    }
+}
+
+impl Display for Float {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Always include a decimal point so floats can't be confused with integers
+        let value = self.0.to_string();
+        f.write_str(&value)?;
+        if !value.contains('.') {
+            f.write_str(".0")?;
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -22,23 +95,31 @@ pub enum FileMode {
     Open,
     Write,
     Append,
+    Tee,
 }
 
 impl Value {
     pub fn get_type(&self) -> Type {
         match self {
             Value::Void => Type::Void,
+            Value::Null => Type::Json,
             Value::String(_) => Type::String,
             Value::Integer(_) => Type::Integer,
+            Value::Float(_) => Type::Float,
+            Value::Char(_) => Type::Char,
             Value::Boolean(_) => Type::Boolean,
             Value::Command(_, _) => Type::Command,
             Value::Array(_, value_type, mutable) => {
                 Type::Array(value_type.clone().into(), *mutable)
             }
+            Value::Map(_, value_type, mutable) => Type::Map(value_type.clone().into(), *mutable),
             Value::Tuple(values) => {
                 Type::Tuple(values.iter().map(|x| x.get_type()).collect::<Vec<_>>())
             }
             Value::FileHandle(_, _) => Type::FileHandle,
+            Value::Function(_, arguments, return_type) => {
+                Type::Function(arguments.clone(), return_type.clone().into())
+            }
         }
     }
 
@@ -51,7 +132,7 @@ impl Value {
             .into_iter()
             .map(|value| {
                 let value = value.into();
-                if value.get_type() != array_type {
+                if !value.get_type().is_assignable_to(&array_type) {
                     Err("Array item did not match array type")
                 } else {
                     Ok(value)
@@ -65,18 +146,44 @@ impl Value {
             mutable,
         ))
     }
+
+    pub fn new_map<I: IntoIterator<Item = (String, T)>, T: Into<Value>>(
+        entries: I,
+        map_type: Type,
+        mutable: bool,
+    ) -> Result<Value, ExecutionError> {
+        let entries = entries
+            .into_iter()
+            .map(|(key, value)| {
+                let value = value.into();
+                if !value.get_type().is_assignable_to(&map_type) {
+                    Err("Map value did not match map type")
+                } else {
+                    Ok((key, value))
+                }
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        Ok(Self::Map(Rc::new(RefCell::new(entries)), map_type, mutable))
+    }
 }
 
 impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Value::Void => f.write_str("void")?,
+            Value::Null => f.write_str("null")?,
             Value::String(data) => {
                 f.write_str("\"")?;
                 f.write_str(&data.replace("\"", "\\\""))?;
                 f.write_str("\"")?;
             }
             Value::Integer(data) => data.fmt(f)?,
+            Value::Float(data) => data.fmt(f)?,
+            Value::Char(data) => match data {
+                '\'' => f.write_str("'\\''")?,
+                data => write!(f, "'{data}'")?,
+            },
             Value::Boolean(data) => data.fmt(f)?,
             Value::Command(program, arguments) => {
                 let combined = Some(program)
@@ -86,16 +193,32 @@ impl Display for Value {
                 fmt_collection("`", " ", "`", combined, f)?
             }
             Value::Array(data, _, _) => fmt_collection("[", ",", "]", data.borrow().iter(), f)?,
+            Value::Map(data, _, _) => {
+                // Sort the entries so that formatting a map is deterministic
+                let data = data.borrow();
+                let mut entries = data.iter().collect::<Vec<_>>();
+                entries.sort_by_key(|(left, _)| *left);
+                let entries = entries
+                    .into_iter()
+                    .map(|(key, value)| format!("{}:{}", Value::String(key.to_owned()), value));
+                fmt_collection("{", ",", "}", entries, f)?
+            }
             Value::Tuple(data) => fmt_collection("(", ",", ")", data.iter(), f)?,
             Value::FileHandle(path, mode) => {
                 match mode {
                     FileMode::Open => f.write_str("<file_handle:open(")?,
                     FileMode::Write => f.write_str("<file_handle:write(")?,
                     FileMode::Append => f.write_str("<file_handle:append(")?,
+                    FileMode::Tee => f.write_str("<file_handle:tee(")?,
                 };
                 Value::String(path.to_owned()).fmt(f)?;
                 f.write_str(")>")?;
             }
+            Value::Function(name, _, _) => {
+                f.write_str("<function:")?;
+                f.write_str(name)?;
+                f.write_str(">")?;
+            }
         };
 
         return Ok(());
@@ -114,6 +237,18 @@ impl From<i32> for Value {
     }
 }
 
+impl From<Float> for Value {
+    fn from(value: Float) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<char> for Value {
+    fn from(value: char) -> Self {
+        Value::Char(value)
+    }
+}
+
 impl From<bool> for Value {
     fn from(value: bool) -> Self {
         Value::Boolean(value)
@@ -125,11 +260,17 @@ pub enum Type {
     Void,
     String,
     Integer,
+    Float,
+    Char,
     Boolean,
     Command,
     Array(Box<Self>, bool),
+    Map(Box<Self>, bool),
     Tuple(Vec<Self>),
     FileHandle,
+    Function(Vec<Self>, Box<Self>),
+    /// Values parsed from json, which can be any json compatible value at runtime.
+    Json,
 }
 
 impl Display for Type {
@@ -138,6 +279,8 @@ impl Display for Type {
             Type::Void => f.write_str("void"),
             Type::String => f.write_str("string"),
             Type::Integer => f.write_str("integer"),
+            Type::Float => f.write_str("float"),
+            Type::Char => f.write_str("char"),
             Type::Boolean => f.write_str("boolean"),
             Type::Command => f.write_str("command"),
             Type::Array(array_type, mutable) => {
@@ -150,8 +293,23 @@ impl Display for Type {
 
                 Ok(())
             }
+            Type::Map(map_type, mutable) => {
+                if *mutable {
+                    f.write_str("mut ")?;
+                }
+                f.write_str("{")?;
+                map_type.fmt(f)?;
+                f.write_str("}")?;
+
+                Ok(())
+            }
             Type::Tuple(item_types) => fmt_collection("(", ",", ")", item_types.iter(), f),
             Type::FileHandle => f.write_str("file_handle"),
+            Type::Function(arguments, return_type) => {
+                fmt_collection("func(", ",", "): ", arguments.iter(), f)?;
+                return_type.fmt(f)
+            }
+            Type::Json => f.write_str("json"),
         }
     }
 }
@@ -160,6 +318,48 @@ impl Type {
     pub fn is_assignable_to(&self, other: &Type) -> bool {
         // This method is dumb right now, but keeping it in to make it easier when we implement
         // mutable types being able to be assigned to non-mutable values
+        if *other == Type::Json {
+            return self.is_json_compatible();
+        }
+
         self == other
     }
+
+    /// Compares types the same way as `==`, except that collections at any level of nesting are
+    /// equal whether or not they are mutable.
+    pub fn eq_ignoring_mutability(&self, other: &Type) -> bool {
+        let all_eq = |left: &[Type], right: &[Type]| {
+            left.len() == right.len()
+                && left
+                    .iter()
+                    .zip(right)
+                    .all(|(left, right)| left.eq_ignoring_mutability(right))
+        };
+
+        match (self, other) {
+            (Type::Array(left, _), Type::Array(right, _))
+            | (Type::Map(left, _), Type::Map(right, _)) => left.eq_ignoring_mutability(right),
+            (Type::Tuple(left), Type::Tuple(right)) => all_eq(left, right),
+            (Type::Function(left_args, left_return), Type::Function(right_args, right_return)) => {
+                all_eq(left_args, right_args) && left_return.eq_ignoring_mutability(right_return)
+            }
+            (left, right) => left == right,
+        }
+    }
+
+    pub fn is_json_compatible(&self) -> bool {
+        match self {
+            Type::String
+            | Type::Integer
+            | Type::Float
+            | Type::Char
+            | Type::Boolean
+            | Type::Json => true,
+            Type::Array(inner_type, _) | Type::Map(inner_type, _) => {
+                inner_type.is_json_compatible()
+            }
+            Type::Tuple(item_types) => item_types.iter().all(|x| x.is_json_compatible()),
+            _ => false,
+        }
+    }
 }
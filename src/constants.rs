@@ -16,6 +16,9 @@ macro_rules! count {
 }
 
 pub const UNDERSCORE: &str = "_";
+pub const DEFAULT: &str = "default";
+pub const SUBST: &str = "subst";
+pub const MERGE: &str = "merge";
 
 define_keywords!(
     IF => "if",
@@ -28,11 +31,15 @@ define_keywords!(
     FOR => "for",
     IN => "in",
     WHILE => "while",
+    DO => "do",
     RETURN => "return",
     BREAK => "break",
     CONTINUE => "continue",
     EXIT => "exit",
     CAP => "cap",
     AS => "as",
-    MUT => "mut"
+    MUT => "mut",
+    MATCH => "match",
+    TRY => "try",
+    CATCH => "catch"
 );
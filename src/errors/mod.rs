@@ -2,7 +2,7 @@ use std::{error::Error, fmt::Display};
 
 use serde::Serialize;
 
-use crate::lexer::Token;
+use crate::{lexer::Token, utils::formatting};
 
 macro_rules! impl_error {
     ($error:ident $(,$exit_code:expr)?) => {
@@ -65,6 +65,10 @@ macro_rules! nash_error {
                     Self::Other(_) => 100
                 }
             }
+
+            pub fn to_json(&self) -> String {
+                serde_json::to_string(self).expect("Unable to serialize error")
+            }
         }
 
         impl Display for NashError {
@@ -121,6 +125,11 @@ pub struct ParserError {
     pub token: String,
     pub start: Option<usize>,
     pub end: Option<usize>,
+    /// Only filled in by `check`, as it has no excerpt of the script to show where the error is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
 }
 
 impl ParserError {
@@ -130,6 +139,8 @@ impl ParserError {
             token: String::new(),
             start: None,
             end: None,
+            line: None,
+            column: None,
         }
     }
 
@@ -138,6 +149,20 @@ impl ParserError {
         self.start = Some(token.start);
         self.end = Some(token.end);
     }
+
+    /// The 1-based line and column of the token the error happened at, within the script it was
+    /// parsed from.
+    pub fn line_column(&self, source: &str) -> Option<(usize, usize)> {
+        self.start
+            .map(|start| formatting::line_column(source, start))
+    }
+
+    pub fn set_line_column(&mut self, source: &str) {
+        if let Some((line, column)) = self.line_column(source) {
+            self.line = Some(line);
+            self.column = Some(column);
+        }
+    }
 }
 
 impl Display for ParserError {
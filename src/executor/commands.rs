@@ -11,6 +11,14 @@ pub struct CommandDefinition {
     pub program: String,
     pub arguments: Vec<String>,
     pub capture_stderr: bool,
+    /// Sends stderr to the same place as stdout, so it gets piped into the next command or
+    /// written to the destination along with the normal output.
+    pub merge_stderr: bool,
+    /// Keeps a copy of the stdout of the command, even if it is piped into another command.
+    pub capture_stdout: bool,
+    /// Commands that are run before this one, with the path to a temporary file containing the
+    /// output of each of them added to the end of the arguments.
+    pub substitutions: Vec<CommandDefinition>,
 }
 
 impl CommandDefinition {
@@ -19,8 +27,26 @@ impl CommandDefinition {
             program,
             arguments,
             capture_stderr,
+            merge_stderr: false,
+            capture_stdout: false,
+            substitutions: Vec::new(),
         }
     }
+
+    pub fn with_merged_stderr(mut self) -> Self {
+        self.merge_stderr = true;
+        self
+    }
+
+    pub fn with_captured_stdout(mut self) -> Self {
+        self.capture_stdout = true;
+        self
+    }
+
+    pub fn with_substitution(mut self, substitution: CommandDefinition) -> Self {
+        self.substitutions.push(substitution);
+        self
+    }
 }
 
 impl From<&str> for CommandDefinition {
@@ -73,6 +99,8 @@ pub enum PipelineSource {
 pub enum PipelineDestination {
     FileWrite(String),
     FileAppend(String),
+    /// Writes the output to the file, but also returns it as the value of the pipeline.
+    Tee(String),
 }
 
 #[derive(Debug, Clone)]
@@ -97,11 +125,38 @@ impl PipelineOutput {
 pub struct CommandOutput {
     pub exit_code: u8,
     pub stderr: Option<String>,
+    /// Only set for commands that had their stdout captured.
+    pub stdout: Option<String>,
+    /// Set when the command was killed for exceeding the command timeout, the exit code is
+    /// [`CommandOutput::TIMED_OUT_EXIT_CODE`] in that case.
+    pub timed_out: bool,
 }
 
 impl CommandOutput {
+    /// Same exit code that coreutils `timeout` uses for commands that it had to kill.
+    pub const TIMED_OUT_EXIT_CODE: u8 = 124;
+
     pub fn new(exit_code: u8, stderr: Option<String>) -> Self {
-        Self { exit_code, stderr }
+        Self {
+            exit_code,
+            stderr,
+            stdout: None,
+            timed_out: false,
+        }
+    }
+
+    pub fn with_stdout(mut self, stdout: String) -> Self {
+        self.stdout = Some(stdout);
+        self
+    }
+
+    pub fn timed_out(stderr: Option<String>) -> Self {
+        Self {
+            exit_code: Self::TIMED_OUT_EXIT_CODE,
+            stderr,
+            stdout: None,
+            timed_out: true,
+        }
     }
 }
 
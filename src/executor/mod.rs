@@ -1,22 +1,111 @@
-use std::io::{self, stderr, stdin, stdout, BufRead, BufReader, Stderr, Stdin, Stdout, Write};
+use std::{
+    env, fs,
+    io::{self, stderr, stdin, stdout, BufRead, BufReader, Stderr, Stdin, Stdout, Write},
+    path::Path,
+    time::Duration,
+};
 
 use commands::{Pipeline, PipelineOutput};
 
 pub mod commands;
 mod system_command_executor;
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExecutorOptions {
     pub max_call_stack_depth: usize,
+    /// Command output is kept byte for byte by default, setting this strips any trailing
+    /// whitespace (such as the final newline most programs print) from captured stdout.
+    pub trim_command_output: bool,
+    /// Stops errors from being printed to stderr in the human readable format, so that the caller
+    /// can print them as json (see `NashError::to_json`) for editors and other tooling.
+    pub json_errors: bool,
+    /// Paths that aren't valid utf-8 are skipped by glob with a warning on stderr, instead of
+    /// failing the whole call.
+    pub skip_invalid_glob_paths: bool,
+    /// Commands still running once the whole pipeline has taken longer than this are killed, and
+    /// the pipeline fails with a timeout error.
+    pub command_timeout: Option<Duration>,
+    /// Prints a summary to stderr once a script finishes of how much time was spent running
+    /// commands compared to evaluating the script itself.
+    pub profile: bool,
 }
 
-impl ExecutorOptions {
-    pub fn default() -> Self {
+impl Default for ExecutorOptions {
+    fn default() -> Self {
         Self {
             max_call_stack_depth: 64,
+            trim_command_output: false,
+            json_errors: false,
+            skip_invalid_glob_paths: false,
+            command_timeout: None,
+            profile: false,
         }
     }
 }
 
+impl ExecutorOptions {
+    pub fn builder() -> ExecutorOptionsBuilder {
+        ExecutorOptionsBuilder {
+            options: Self::default(),
+        }
+    }
+}
+
+/// Builds [`ExecutorOptions`], any option that isn't set keeps its default value.
+pub struct ExecutorOptionsBuilder {
+    options: ExecutorOptions,
+}
+
+impl ExecutorOptionsBuilder {
+    pub fn max_call_stack_depth(mut self, max_call_stack_depth: usize) -> Self {
+        self.options.max_call_stack_depth = max_call_stack_depth;
+        self
+    }
+
+    pub fn trim_command_output(mut self, trim_command_output: bool) -> Self {
+        self.options.trim_command_output = trim_command_output;
+        self
+    }
+
+    pub fn json_errors(mut self, json_errors: bool) -> Self {
+        self.options.json_errors = json_errors;
+        self
+    }
+
+    pub fn skip_invalid_glob_paths(mut self, skip_invalid_glob_paths: bool) -> Self {
+        self.options.skip_invalid_glob_paths = skip_invalid_glob_paths;
+        self
+    }
+
+    pub fn command_timeout(mut self, command_timeout: Duration) -> Self {
+        self.options.command_timeout = Some(command_timeout);
+        self
+    }
+
+    pub fn profile(mut self, profile: bool) -> Self {
+        self.options.profile = profile;
+        self
+    }
+
+    pub fn build(self) -> ExecutorOptions {
+        self.options
+    }
+}
+
+/// Time spent running command pipelines, used to split up where time went when profiling.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionProfile {
+    pub pipeline_time: Duration,
+    pub pipeline_count: usize,
+}
+
+impl ExecutionProfile {
+    pub fn record_pipeline(&mut self, duration: Duration) {
+        self.pipeline_time += duration;
+        self.pipeline_count += 1;
+    }
+}
+
 pub trait Executor
 where
     Self::Stdin: BufRead,
@@ -32,10 +121,23 @@ where
     fn stderr(&mut self) -> &mut Self::Stderr;
     fn run_pipeline(&self, pipeline: &Pipeline) -> io::Result<PipelineOutput>;
     fn options(&self) -> &ExecutorOptions;
+    fn profile(&mut self) -> &mut ExecutionProfile;
+    /// Returns the names of the entries in a directory, not their full paths.
+    fn list_dir(&self, path: &str) -> io::Result<Vec<String>>;
+    fn is_dir(&self, path: &str) -> bool;
+    fn is_file(&self, path: &str) -> bool;
+    fn get_env(&self, name: &str) -> Option<String>;
+    fn set_env(&mut self, name: &str, value: &str);
+    fn read_file(&self, path: &str) -> io::Result<String>;
+    fn write_file(&mut self, path: &str, contents: &str) -> io::Result<()>;
+    /// Arguments passed to the script, not including the script path itself.
+    fn args(&self) -> Vec<String>;
 }
 
 pub struct SystemExecutor {
     options: ExecutorOptions,
+    profile: ExecutionProfile,
+    args: Vec<String>,
     stdin: <SystemExecutor as Executor>::Stdin,
     stdout: <SystemExecutor as Executor>::Stdout,
     stderr: <SystemExecutor as Executor>::Stderr,
@@ -45,11 +147,18 @@ impl SystemExecutor {
     pub fn new(options: ExecutorOptions) -> Self {
         Self {
             options,
+            profile: ExecutionProfile::default(),
+            args: Vec::new(),
             stdin: BufReader::new(stdin()),
             stdout: stdout(),
             stderr: stderr(),
         }
     }
+
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
 }
 
 impl Executor for SystemExecutor {
@@ -72,10 +181,56 @@ impl Executor for SystemExecutor {
     }
 
     fn run_pipeline(&self, pipeline: &Pipeline) -> io::Result<PipelineOutput> {
-        system_command_executor::run_pipeline(pipeline)
+        system_command_executor::run_pipeline(pipeline, &self.options)
     }
 
     fn options(&self) -> &ExecutorOptions {
         &self.options
     }
+
+    fn profile(&mut self) -> &mut ExecutionProfile {
+        &mut self.profile
+    }
+
+    fn list_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        fs::read_dir(path)?
+            .map(|entry| {
+                entry?.file_name().into_string().map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "File name is not in valid utf-8 encoding",
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn is_dir(&self, path: &str) -> bool {
+        Path::new(path).is_dir()
+    }
+
+    fn is_file(&self, path: &str) -> bool {
+        Path::new(path).is_file()
+    }
+
+    fn get_env(&self, name: &str) -> Option<String> {
+        env::var(name).ok()
+    }
+
+    fn set_env(&mut self, name: &str, value: &str) {
+        // Setting the variable on our own process means any commands we run will inherit it
+        env::set_var(name, value)
+    }
+
+    fn read_file(&self, path: &str) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn write_file(&mut self, path: &str, contents: &str) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+
+    fn args(&self) -> Vec<String> {
+        self.args.clone()
+    }
 }
@@ -1,11 +1,27 @@
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
 use std::{
-    fs::{File, OpenOptions},
-    io::{self, Read},
+    collections::hash_map::RandomState,
+    env,
+    fs::{self, File, OpenOptions},
+    hash::{BuildHasher, Hasher},
+    io::{self, PipeReader, Read, Write},
+    path::PathBuf,
     process::{self, Child, ChildStdout, Command, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
-use super::commands::{
-    CommandDefinition, CommandOutput, Pipeline, PipelineDestination, PipelineOutput, PipelineSource,
+use super::{
+    commands::{
+        CommandDefinition, CommandOutput, Pipeline, PipelineDestination, PipelineOutput,
+        PipelineSource,
+    },
+    ExecutorOptions,
 };
 
 enum InputType {
@@ -13,6 +29,7 @@ enum InputType {
     Literal(String),
     File(File),
     ChildStdout(ChildStdout),
+    Pipe(PipeReader),
 }
 
 impl InputType {
@@ -26,6 +43,7 @@ impl InputType {
             }
             InputType::File(file) => Stdio::from(file),
             InputType::ChildStdout(stdout) => Stdio::from(stdout),
+            InputType::Pipe(reader) => Stdio::from(reader),
         };
 
         command.stdin(stdin);
@@ -47,7 +65,7 @@ impl InputType {
             InputType::File(mut source_file) => {
                 std::io::copy(&mut source_file, &mut destination_to_file(destination)?)?;
             }
-            InputType::ChildStdout(_) => {
+            InputType::ChildStdout(_) | InputType::Pipe(_) => {
                 panic!("Process output should be directly sent to destination, instead of using a pipe")
             }
         }
@@ -68,6 +86,9 @@ impl InputType {
             InputType::ChildStdout(mut stdout) => {
                 stdout.read_to_string(&mut buf)?;
             }
+            InputType::Pipe(mut reader) => {
+                reader.read_to_string(&mut buf)?;
+            }
         }
 
         Ok(buf)
@@ -91,45 +112,243 @@ impl ProcessCallback {
     }
 }
 
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 enum OutputType {
     File(File),
     Pipe(),
 }
 
-pub fn run_pipeline(pipeline: &Pipeline) -> io::Result<PipelineOutput> {
-    let (mut processes, final_output) = spawn_processes(pipeline)?;
+type StdoutCapture = JoinHandle<io::Result<String>>;
 
-    let stdout = if let Some(destination) = &pipeline.destination {
-        final_output.write_to_destination(destination)?;
-        None
-    } else {
-        Some(final_output.write_to_string()?)
+static NEXT_SUBSTITUTION_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Temporary files holding the output of substituted commands, which get deleted once the
+/// pipeline using them has finished.
+struct SubstitutionFiles {
+    paths: Vec<PathBuf>,
+    timeout: Option<Duration>,
+}
+
+impl SubstitutionFiles {
+    fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            paths: Vec::new(),
+            timeout,
+        }
+    }
+
+    /// Runs the command to completion with its stdout written to a new temporary file, and
+    /// returns the path to that file.
+    fn run(&mut self, definition: &CommandDefinition) -> io::Result<PathBuf> {
+        let (path, file) = create_private_file()?;
+        self.paths.push(path.clone());
+
+        let process = process::Command::new(&definition.program)
+            .args(&definition.arguments)
+            .stdin(Stdio::null())
+            .stdout(file)
+            .spawn()?;
+
+        let processes = Mutex::new(vec![process]);
+        let timed_out = match self.timeout {
+            Some(timeout) => !kill_after_timeout(&processes, timeout)?.is_empty(),
+            None => false,
+        };
+        let mut processes = processes
+            .into_inner()
+            .map_err(|_| io::Error::other("Unable to access running commands"))?;
+        let status = processes[0].wait()?;
+
+        if timed_out {
+            return Err(io::Error::other(format!(
+                "Substituted command {} timed out",
+                definition.program
+            )));
+        }
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "Substituted command {} failed with {status}",
+                definition.program
+            )));
+        }
+
+        Ok(path)
+    }
+}
+
+/// Creates a new file in the temp directory that only the current user can read. The name is
+/// randomised and the file must not already exist, so another user can't read the output or
+/// redirect it somewhere else by creating the file (or a symlink) first.
+fn create_private_file() -> io::Result<(PathBuf, File)> {
+    loop {
+        let id = NEXT_SUBSTITUTION_ID.fetch_add(1, Ordering::Relaxed);
+        let random = RandomState::new().build_hasher().finish();
+        let path = env::temp_dir().join(format!("nash-subst-{}-{id}-{random:016x}", process::id()));
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+impl Drop for SubstitutionFiles {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+pub fn run_pipeline(pipeline: &Pipeline, options: &ExecutorOptions) -> io::Result<PipelineOutput> {
+    let mut substitution_files = SubstitutionFiles::new(options.command_timeout);
+    let (mut processes, stdout_captures, final_output) =
+        spawn_processes(pipeline, &mut substitution_files)?;
+    let stderr_handles = processes
+        .iter_mut()
+        .map(|process| process.stderr.take())
+        .collect::<Vec<_>>();
+
+    // The timeout is enforced from a separate thread, as reading the output below blocks until
+    // every process has closed its end of the pipes
+    let processes = Arc::new(Mutex::new(processes));
+    let watchdog = options.command_timeout.map(|timeout| {
+        let processes = processes.clone();
+        thread::spawn(move || kill_after_timeout(&processes, timeout))
+    });
+
+    let stdout = match &pipeline.destination {
+        Some(PipelineDestination::Tee(_)) | None => {
+            let mut stdout_data = final_output.write_to_string()?;
+            if let Some(destination) = &pipeline.destination {
+                // The file gets exactly what was written, even if the returned output is trimmed
+                destination_to_file(destination)?.write_all(stdout_data.as_bytes())?;
+            }
+            if options.trim_command_output {
+                stdout_data.truncate(stdout_data.trim_end().len());
+            }
+            Some(stdout_data)
+        }
+        Some(destination) => {
+            final_output.write_to_destination(destination)?;
+            None
+        }
     };
 
-    let mut outputs = Vec::new();
-    for process in &mut processes {
+    let mut stderr_outputs = Vec::new();
+    for stderr in stderr_handles {
         let mut stderr_data = None;
-        if let Some(mut stderr) = process.stderr.take() {
+        if let Some(mut stderr) = stderr {
             let mut buffer = String::new();
             stderr.read_to_string(&mut buffer)?;
             stderr_data = Some(buffer);
         }
+        stderr_outputs.push(stderr_data);
+    }
+
+    let mut captured_stdouts = Vec::new();
+    for capture in stdout_captures {
+        let mut captured = match capture {
+            Some(capture) => Some(
+                capture
+                    .join()
+                    .map_err(|_| io::Error::other("Command stdout capture thread panicked"))??,
+            ),
+            None => None,
+        };
+        if let (Some(captured), true) = (&mut captured, options.trim_command_output) {
+            captured.truncate(captured.trim_end().len());
+        }
+        captured_stdouts.push(captured);
+    }
 
+    let killed = match watchdog {
+        Some(watchdog) => watchdog
+            .join()
+            .map_err(|_| io::Error::other("Command timeout thread panicked"))??,
+        None => Vec::new(),
+    };
+
+    let mut processes = processes
+        .lock()
+        .map_err(|_| io::Error::other("Command timeout thread panicked"))?;
+
+    let mut outputs = Vec::new();
+    let results = processes
+        .iter_mut()
+        .zip(stderr_outputs)
+        .zip(captured_stdouts)
+        .enumerate();
+    for (index, ((process, stderr_data), stdout_data)) in results {
+        // Always wait on the process, even if it was killed, so it doesn't get left as a zombie
         let status = process.wait()?;
+        if killed.contains(&index) {
+            let mut output = CommandOutput::timed_out(stderr_data);
+            output.stdout = stdout_data;
+            outputs.push(output);
+            continue;
+        }
+
         let status_code = status
             .code()
             .ok_or(io::Error::other("Unable to get exit code for command"))?
             .try_into()
             .map_err(|_| io::Error::other("Exit code was not between 0 and 255"))?;
 
-        outputs.push(CommandOutput::new(status_code, stderr_data));
+        let mut output = CommandOutput::new(status_code, stderr_data);
+        output.stdout = stdout_data;
+        outputs.push(output);
     }
 
     return Ok(PipelineOutput::new(stdout, outputs));
 }
 
-fn spawn_processes(pipeline: &Pipeline) -> io::Result<(Vec<Child>, InputType)> {
+/// Polls the processes until they have all exited, killing any that are still running once the
+/// timeout has passed. Returns the indexes of the processes that had to be killed.
+fn kill_after_timeout(processes: &Mutex<Vec<Child>>, timeout: Duration) -> io::Result<Vec<usize>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let mut processes = processes
+            .lock()
+            .map_err(|_| io::Error::other("Unable to access running commands"))?;
+
+        let mut running = Vec::new();
+        for (index, process) in processes.iter_mut().enumerate() {
+            if process.try_wait()?.is_none() {
+                running.push(index);
+            }
+        }
+
+        if running.is_empty() {
+            return Ok(running);
+        }
+
+        if Instant::now() >= deadline {
+            for index in &running {
+                // The process could have exited since we checked, which is fine to ignore
+                let _ = processes[*index].kill();
+            }
+            return Ok(running);
+        }
+
+        drop(processes);
+        thread::sleep(POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now())));
+    }
+}
+
+fn spawn_processes(
+    pipeline: &Pipeline,
+    substitution_files: &mut SubstitutionFiles,
+) -> io::Result<(Vec<Child>, Vec<Option<StdoutCapture>>, InputType)> {
     let mut processes = Vec::new();
+    let mut stdout_captures = Vec::new();
     let mut input = get_input_type(pipeline)?;
     let mut command_definitions = pipeline.commands.iter().peekable();
     while let Some(command_definition) = command_definitions.next() {
@@ -141,43 +360,124 @@ fn spawn_processes(pipeline: &Pipeline) -> io::Result<(Vec<Child>, InputType)> {
             get_output_type(pipeline)?
         };
 
-        let mut process = spawn_process(command_definition, input, output)?;
-
-        if let Some(stdout) = process.stdout.take() {
-            input = InputType::ChildStdout(stdout);
+        if command_definition.capture_stdout {
+            // Output goes through a pipe we read from first, so we can keep a copy of it before
+            // passing it on to wherever it was meant to go
+            let (process, stdout) = spawn_process(
+                command_definition,
+                input,
+                OutputType::Pipe(),
+                substitution_files,
+            )?;
+            let (next_input, capture) = capture_stdout(stdout, output)?;
+            input = next_input;
+            processes.push(process);
+            stdout_captures.push(Some(capture));
         } else {
-            input = InputType::Null();
+            let (process, next_input) =
+                spawn_process(command_definition, input, output, substitution_files)?;
+            input = next_input;
+            processes.push(process);
+            stdout_captures.push(None);
         }
-
-        processes.push(process);
     }
 
-    return Ok((processes, input));
+    Ok((processes, stdout_captures, input))
+}
+
+fn capture_stdout(stdout: InputType, output: OutputType) -> io::Result<(InputType, StdoutCapture)> {
+    let mut source: Box<dyn Read + Send> = match stdout {
+        InputType::ChildStdout(stdout) => Box::new(stdout),
+        InputType::Pipe(reader) => Box::new(reader),
+        _ => return Err(io::Error::other("Unable to capture stdout of command")),
+    };
+
+    let (next_input, mut sink): (InputType, Box<dyn Write + Send>) = match output {
+        OutputType::File(file) => (InputType::Null(), Box::new(file)),
+        OutputType::Pipe() => {
+            let (reader, writer) = io::pipe()?;
+            (InputType::Pipe(reader), Box::new(writer))
+        }
+    };
+
+    let capture = thread::spawn(move || {
+        let mut captured = Vec::new();
+        let mut buffer = [0; 8192];
+        let mut sink_open = true;
+        loop {
+            let read = source.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+
+            captured.extend_from_slice(&buffer[..read]);
+            // The next command might exit without reading everything, we still want to keep
+            // reading so the captured command doesn't get blocked writing its output
+            if sink_open && sink.write_all(&buffer[..read]).is_err() {
+                sink_open = false;
+            }
+        }
+
+        Ok(String::from_utf8_lossy(&captured).into_owned())
+    });
+
+    Ok((next_input, capture))
 }
 
 fn spawn_process(
     definition: &CommandDefinition,
     input: InputType,
     output: OutputType,
-) -> io::Result<Child> {
+    substitution_files: &mut SubstitutionFiles,
+) -> io::Result<(Child, InputType)> {
     let mut command = process::Command::new(definition.program.to_owned());
     command.args(definition.arguments.to_owned());
+    for substitution in &definition.substitutions {
+        command.arg(substitution_files.run(substitution)?);
+    }
     let process_callback = input.write_to_command(&mut command);
 
-    // Stdout depends on what kind of output we need to provide
-    command.stdout(match output {
-        OutputType::File(file) => Stdio::from(file),
-        OutputType::Pipe() => Stdio::piped(),
-    });
-
-    if definition.capture_stderr {
-        command.stderr(Stdio::piped());
+    // Merged stderr can't use the pipe std creates for stdout, as there is no way to give the
+    // same pipe to both, so we create the pipe ourselves instead
+    let mut merged_output = None;
+    if definition.merge_stderr {
+        let (stdout, stderr) = match output {
+            OutputType::File(file) => (Stdio::from(file.try_clone()?), Stdio::from(file)),
+            OutputType::Pipe() => {
+                let (reader, writer) = io::pipe()?;
+                merged_output = Some(reader);
+                (Stdio::from(writer.try_clone()?), Stdio::from(writer))
+            }
+        };
+        command.stdout(stdout);
+        command.stderr(stderr);
+    } else {
+        // Stdout depends on what kind of output we need to provide
+        command.stdout(match output {
+            OutputType::File(file) => Stdio::from(file),
+            OutputType::Pipe() => Stdio::piped(),
+        });
+
+        if definition.capture_stderr {
+            command.stderr(Stdio::piped());
+        }
     }
 
     let mut process = command.spawn()?;
+    // The command holds on to our copy of the write end of any merged pipe, which needs to be
+    // closed so that reading from it finishes once the process exits
+    drop(command);
     process_callback.write_to_process(&mut process)?;
 
-    return Ok(process);
+    let next_input = if let Some(reader) = merged_output {
+        InputType::Pipe(reader)
+    } else if let Some(stdout) = process.stdout.take() {
+        InputType::ChildStdout(stdout)
+    } else {
+        InputType::Null()
+    };
+
+    Ok((process, next_input))
 }
 
 fn get_input_type(pipeline: &Pipeline) -> io::Result<InputType> {
@@ -193,14 +493,17 @@ fn get_input_type(pipeline: &Pipeline) -> io::Result<InputType> {
 
 fn get_output_type(pipeline: &Pipeline) -> io::Result<OutputType> {
     Ok(match &pipeline.destination {
+        // Tee needs to read the output itself so it can also be returned
+        Some(PipelineDestination::Tee(_)) | None => OutputType::Pipe(),
         Some(destination) => OutputType::File(destination_to_file(destination)?),
-        None => OutputType::Pipe(),
     })
 }
 
 fn destination_to_file(destination: &PipelineDestination) -> io::Result<File> {
     Ok(match destination {
-        PipelineDestination::FileWrite(path) => File::create(path)?,
+        PipelineDestination::FileWrite(path) | PipelineDestination::Tee(path) => {
+            File::create(path)?
+        }
         PipelineDestination::FileAppend(path) => OpenOptions::new().append(true).open(path)?,
     })
 }
@@ -25,7 +25,12 @@ impl<'a> Token<'a> {
 enum LexerContext {
     Root,
     Comment,
+    /// The nesting depth of the comment, and whether the next grapheme is the second half of a
+    /// `/*` or `*/` that has already been handled.
+    BlockComment(u32, bool),
     String(bool),
+    RawString,
+    Char(bool),
     Command,
     TemplateExpression(u32),
 }
@@ -39,6 +44,9 @@ pub struct Tokens<'a> {
     next: (usize, &'a str),
     iterator: Peekable<GraphemeIndices<'a>>,
     context_stack: Vec<LexerContext>,
+    /// Where each context in the context stack was started, so that we can point to it if it is
+    /// never closed.
+    context_starts: Vec<usize>,
 }
 
 impl<'a> Tokens<'a> {
@@ -48,6 +56,7 @@ impl<'a> Tokens<'a> {
             next: (0, ""),
             iterator: code.grapheme_indices(true).peekable(),
             context_stack: vec![LexerContext::Root],
+            context_starts: vec![0],
         }
     }
 }
@@ -57,7 +66,7 @@ impl<'a> Tokens<'a> {
         loop {
             // If we are at the end of the file then the iterator is finished.
             let Some(next) = self.iterator.peek() else {
-                return None;
+                return self.unterminated_context_error().map(Err);
             };
 
             self.next = *next;
@@ -87,7 +96,12 @@ impl<'a> Tokens<'a> {
             let value = &self.buffer[start..end];
 
             // Try to parse the next token
-            match tokens::try_get_token(&mut self.context_stack, value) {
+            let token_result =
+                tokens::try_get_token(&mut self.context_stack, value, &self.buffer[end..]);
+            self.context_starts.truncate(self.context_stack.len());
+            self.context_starts.resize(self.context_stack.len(), start);
+
+            match token_result {
                 tokens::GetTokenResult::Match(token_kind) => {
                     self.iterator.next();
 
@@ -124,6 +138,45 @@ impl<'a> Tokens<'a> {
     }
 }
 
+impl<'a> Tokens<'a> {
+    /// Reaching the end of the file is only valid if every string, command, template or comment
+    /// has been closed.
+    fn unterminated_context_error(&mut self) -> Option<LexerError> {
+        // The end of the file also ends a line comment, but not whatever the comment is inside
+        let (context, start) = loop {
+            let context = self.context_stack.pop()?;
+            let start = self.context_starts.pop()?;
+            if !matches!(context, LexerContext::Comment) {
+                break (context, start);
+            }
+        };
+        let message = match context {
+            LexerContext::Root | LexerContext::Comment => return None,
+            LexerContext::BlockComment(_, _) => "Unterminated block comment".to_owned(),
+            LexerContext::String(_) | LexerContext::RawString => {
+                format!("Unterminated string literal starting at position {start}")
+            }
+            LexerContext::Char(_) => {
+                format!("Unterminated char literal starting at position {start}")
+            }
+            LexerContext::Command => {
+                format!("Unterminated command literal starting at position {start}")
+            }
+            LexerContext::TemplateExpression(_) => {
+                format!("Unterminated template expression starting at position {start}")
+            }
+        };
+
+        // Clear the rest of the stack so that only one error is returned
+        self.context_stack.clear();
+        self.context_starts.clear();
+
+        let mut err = LexerError::from(message);
+        err.position = Some(start);
+        Some(err)
+    }
+}
+
 impl<'a> Iterator for Tokens<'a> {
     type Item = Result<Token<'a>, LexerError>;
 
@@ -189,4 +242,100 @@ func main() {
         }}!""#,
         ));
     }
+
+    #[test]
+    fn should_skip_nested_block_comments() {
+        assert_yaml_snapshot!(lex_code(
+            r#"
+/* A block comment
+   /* with a nested comment */
+   that spans lines */
+var value = 4 /* inline */ / 2;
+"#,
+        ));
+    }
+
+    #[test]
+    fn should_error_on_unterminated_block_comment() {
+        let result = lex("var value = 1;\n/* never /* closed */\n")
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_err();
+
+        assert_eq!(result.message, "Unterminated block comment");
+        assert_eq!(result.position, Some(15));
+    }
+
+    #[test]
+    fn should_parse_raw_string_without_escapes_or_templates() {
+        assert_yaml_snapshot!(lex_code(r#"var path = r"C:\Users\${not_a_var}";"#));
+    }
+
+    #[test]
+    fn should_parse_empty_raw_string() {
+        assert_yaml_snapshot!(lex_code(r#"var r = r"";"#));
+    }
+
+    #[test]
+    fn should_parse_char_literals() {
+        assert_yaml_snapshot!(lex_code(r#"var c = 'a'; var q = '\'';"#));
+    }
+
+    fn lex_error(code: &str) -> LexerError {
+        lex(code).collect::<Result<Vec<_>, _>>().unwrap_err()
+    }
+
+    #[test]
+    fn should_error_on_unterminated_string() {
+        let result = lex_error("var value = \"never closed;\n");
+
+        assert_eq!(
+            result.message,
+            "Unterminated string literal starting at position 12"
+        );
+        assert_eq!(result.position, Some(12));
+    }
+
+    #[test]
+    fn should_error_on_unterminated_command() {
+        let result = lex_error("exec `ls -la;\n");
+
+        assert_eq!(
+            result.message,
+            "Unterminated command literal starting at position 5"
+        );
+        assert_eq!(result.position, Some(5));
+    }
+
+    #[test]
+    fn should_error_on_unterminated_template_expression() {
+        let result = lex_error("out(\"sum: ${1 + 2\n");
+
+        assert_eq!(
+            result.message,
+            "Unterminated template expression starting at position 10"
+        );
+        assert_eq!(result.position, Some(10));
+    }
+
+    #[test]
+    fn should_error_on_unterminated_template_expression_ending_in_comment() {
+        let result = lex_error("out(\"${1 # c");
+
+        assert_eq!(
+            result.message,
+            "Unterminated template expression starting at position 5"
+        );
+        assert_eq!(result.position, Some(5));
+    }
+
+    #[test]
+    fn should_error_on_unterminated_char() {
+        let result = lex_error("var value = 'a;\n");
+
+        assert_eq!(
+            result.message,
+            "Unterminated char literal starting at position 12"
+        );
+        assert_eq!(result.position, Some(12));
+    }
 }
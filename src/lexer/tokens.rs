@@ -8,6 +8,8 @@ const HASH: &str = "#";
 const BACKSLASH: &str = "\\";
 const NEWLINES: [&str; 2] = ["\n", "\r\n"];
 const DOUBLE_QUOTE: &str = "\"";
+const RAW_QUOTE: &str = "r\"";
+const SINGLE_QUOTE: &str = "'";
 const BACKTICK: &str = "`";
 const EQUALS: &str = "=";
 const PLUS: &str = "+";
@@ -17,6 +19,7 @@ const FORWARD_SLASH: &str = "/";
 const PERCENT: &str = "%";
 const AND: &str = "&";
 const PIPE: &str = "|";
+const CARET: &str = "^";
 const LEFT_BRACKET: &str = "(";
 const RIGHT_BRACKET: &str = ")";
 const LEFT_CURLY: &str = "{";
@@ -97,6 +100,8 @@ define_tokens! {
   ],
   simple => [
     DOUBLE_QUOTE => DoubleQuote,
+    RAW_QUOTE => RawQuote,
+    SINGLE_QUOTE => SingleQuote,
     BACKTICK => Backtick,
     EQUALS => Equals,
     PLUS => Plus,
@@ -106,6 +111,7 @@ define_tokens! {
     PERCENT => Percent,
     AND => And,
     PIPE => Pipe,
+    CARET => Caret,
     LEFT_BRACKET => LeftBracket,
     RIGHT_BRACKET => RightBracket,
     LEFT_CURLY => LeftCurly,
@@ -141,9 +147,15 @@ impl From<TokenKind> for GetTokenResult {
     }
 }
 
-pub fn try_get_token(context_stack: &mut Vec<LexerContext>, current: &str) -> GetTokenResult {
+/// `rest` is everything after `current`, which is needed to tell when a `/` or `*` is the start of
+/// a two character block comment marker.
+pub fn try_get_token(
+    context_stack: &mut Vec<LexerContext>,
+    current: &str,
+    rest: &str,
+) -> GetTokenResult {
     match context_stack.last_mut().unwrap() {
-        LexerContext::Root => default_token_matcher(context_stack, current),
+        LexerContext::Root => default_token_matcher(context_stack, current, rest),
         LexerContext::TemplateExpression(depth) => {
             if current == LEFT_CURLY {
                 *depth += 1;
@@ -155,7 +167,7 @@ pub fn try_get_token(context_stack: &mut Vec<LexerContext>, current: &str) -> Ge
                 context_stack.pop();
             }
 
-            default_token_matcher(context_stack, current)
+            default_token_matcher(context_stack, current, rest)
         }
         LexerContext::Comment => {
             if NEWLINES.contains(&current) {
@@ -163,6 +175,21 @@ pub fn try_get_token(context_stack: &mut Vec<LexerContext>, current: &str) -> Ge
             }
             GetTokenResult::Skip()
         }
+        LexerContext::BlockComment(depth, in_marker) => {
+            if *in_marker {
+                *in_marker = false;
+                if *depth == 0 {
+                    context_stack.pop();
+                }
+            } else if current == FORWARD_SLASH && rest.starts_with(STAR) {
+                *depth += 1;
+                *in_marker = true;
+            } else if current == STAR && rest.starts_with(FORWARD_SLASH) {
+                *depth -= 1;
+                *in_marker = true;
+            }
+            GetTokenResult::Skip()
+        }
         LexerContext::String(is_escaped) => {
             if *is_escaped {
                 *is_escaped = false;
@@ -185,6 +212,34 @@ pub fn try_get_token(context_stack: &mut Vec<LexerContext>, current: &str) -> Ge
                 TokenKind::StringLiteral.into()
             }
         }
+        LexerContext::RawString => {
+            // Nothing is escaped in raw strings, so the first double quote always ends them
+            if current == DOUBLE_QUOTE {
+                context_stack.pop();
+                TokenKind::DoubleQuote.into()
+            } else if current.ends_with(DOUBLE_QUOTE) {
+                GetTokenResult::NoMatch()
+            } else {
+                TokenKind::StringLiteral.into()
+            }
+        }
+        LexerContext::Char(is_escaped) => {
+            // Chars can't contain templates, so only escapes and the closing quote matter
+            if *is_escaped {
+                *is_escaped = false;
+                TokenKind::StringLiteral.into()
+            } else if current.ends_with(BACKSLASH) {
+                *is_escaped = true;
+                TokenKind::StringLiteral.into()
+            } else if current == SINGLE_QUOTE {
+                context_stack.pop();
+                TokenKind::SingleQuote.into()
+            } else if current.ends_with(SINGLE_QUOTE) {
+                GetTokenResult::NoMatch()
+            } else {
+                TokenKind::StringLiteral.into()
+            }
+        }
         LexerContext::Command => {
             if current == DOUBLE_QUOTE {
                 context_stack.push(LexerContext::String(false));
@@ -206,16 +261,33 @@ pub fn try_get_token(context_stack: &mut Vec<LexerContext>, current: &str) -> Ge
     }
 }
 
-fn default_token_matcher(context_stack: &mut Vec<LexerContext>, current: &str) -> GetTokenResult {
+fn default_token_matcher(
+    context_stack: &mut Vec<LexerContext>,
+    current: &str,
+    rest: &str,
+) -> GetTokenResult {
     match current {
         HASH => {
             context_stack.push(LexerContext::Comment);
             GetTokenResult::Skip()
         }
+        FORWARD_SLASH if rest.starts_with(STAR) => {
+            context_stack.push(LexerContext::BlockComment(1, true));
+            GetTokenResult::Skip()
+        }
         DOUBLE_QUOTE => {
             context_stack.push(LexerContext::String(false));
             TokenKind::DoubleQuote.into()
         }
+        // An `r` identifier gets extended to this when it is directly followed by a double quote
+        RAW_QUOTE => {
+            context_stack.push(LexerContext::RawString);
+            TokenKind::RawQuote.into()
+        }
+        SINGLE_QUOTE => {
+            context_stack.push(LexerContext::Char(false));
+            TokenKind::SingleQuote.into()
+        }
         BACKTICK => {
             context_stack.push(LexerContext::Command);
             TokenKind::Backtick.into()
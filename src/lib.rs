@@ -1,15 +1,22 @@
 use std::fmt::{Error, Write};
 use std::io::Read;
 
-use components::ExecutionOutput;
+use components::{ComponentTree, ExecutionOutput};
+pub use components::{CustomBuiltin, Session, Type, Value};
 pub use errors::{ExecutionError, LexerError, NashError, ParserError};
 pub use executor::commands::{
     CommandDefinition, CommandExecutor, CommandOutput, Pipeline, PipelineDestination,
     PipelineOutput, PipelineSource,
 };
 
-pub use executor::{Executor, ExecutorOptions, SystemExecutor};
+pub use lexer::{Token, TokenValue};
 
+pub use executor::{
+    ExecutionProfile, Executor, ExecutorOptions, ExecutorOptionsBuilder, SystemExecutor,
+};
+use utils::formatting::{line_column, line_number_width, number_line};
+
+pub mod ast;
 mod components;
 mod constants;
 mod errors;
@@ -21,14 +28,114 @@ pub fn execute<R: Read, E: Executor>(
     script: &mut R,
     executor: &mut E,
 ) -> Result<ExecutionOutput, NashError> {
+    let print_errors = !executor.options().json_errors;
+    execute_script(script, executor, print_errors)
+}
+
+/// Executes a script in the given session, anything the script declares will still be available
+/// to the scripts executed after it in the same session.
+pub fn execute_in_session<R: Read, E: Executor>(
+    script: &mut R,
+    session: &mut Session,
+    executor: &mut E,
+) -> Result<ExecutionOutput, NashError> {
+    let print_errors = !executor.options().json_errors;
+    execute_script_in_session(script, session, executor, print_errors)
+}
+
+/// Splits a script into tokens, for tools such as syntax highlighters. Each token records the
+/// byte offsets it was found between, and comments and whitespace are skipped.
+///
+/// ```
+/// use nash::TokenValue;
+///
+/// let mut tokens = nash::tokenize("var name = \"nash\";");
+/// let first = tokens.next().unwrap().unwrap();
+/// assert_eq!(first.value, TokenValue::Keyword("var"));
+/// assert_eq!((first.start, first.end), (0, 3));
+/// ```
+pub fn tokenize(script: &str) -> impl Iterator<Item = Result<Token<'_>, LexerError>> {
+    lexer::lex(script)
+}
+
+/// Parses a script without type checking or running it, the syntax tree can be inspected through
+/// `ComponentTree::root` (see the `ast` module for the types it is made of).
+pub fn parse_script(script: &str) -> Result<ComponentTree, NashError> {
+    read_script(&mut script.as_bytes(), false)
+}
+
+/// Parses and type checks a script without running it, returning every type error that could be
+/// found. Lexer and parser errors stop the check straight away, so at most one of those is
+/// returned.
+pub fn check<R: Read>(script: &mut R) -> Result<(), Vec<NashError>> {
+    let content = read_content(script).map_err(|err| vec![err])?;
+    let component_tree = parse_content(&content, false).map_err(|mut err| {
+        if let NashError::ParserError(err) = &mut err {
+            err.set_line_column(&content);
+        }
+        vec![err]
+    })?;
+    let errors = component_tree.post_process_all();
+    if !errors.is_empty() {
+        return Err(errors.into_iter().map(Into::into).collect());
+    }
+
+    Ok(())
+}
+
+fn execute_script<R: Read, E: Executor>(
+    script: &mut R,
+    executor: &mut E,
+    print_errors: bool,
+) -> Result<ExecutionOutput, NashError> {
+    let component_tree = read_script(script, print_errors)?;
+    component_tree.post_process()?;
+
+    let result = component_tree
+        .execute(executor)
+        .map_err(|err| print_execution_error(err, print_errors))?;
+
+    Ok(result)
+}
+
+fn execute_script_in_session<R: Read, E: Executor>(
+    script: &mut R,
+    session: &mut Session,
+    executor: &mut E,
+    print_errors: bool,
+) -> Result<ExecutionOutput, NashError> {
+    let component_tree = read_script(script, print_errors)?;
+
+    let result = session
+        .execute(&component_tree, executor)
+        .map_err(|err| match err {
+            NashError::ExecutionError(err) => print_execution_error(err, print_errors).into(),
+            err => err,
+        })?;
+
+    Ok(result)
+}
+
+fn read_script<R: Read>(script: &mut R, print_errors: bool) -> Result<ComponentTree, NashError> {
+    parse_content(&read_content(script)?, print_errors)
+}
+
+fn read_content<R: Read>(script: &mut R) -> Result<String, NashError> {
     let mut content = String::new();
     script
         .read_to_string(&mut content)
         .map_err(|err| format!("Unable to read script: {err}"))?;
 
-    let tokens = lexer::lex(content.as_str())
+    Ok(content)
+}
+
+fn parse_content(content: &str, print_errors: bool) -> Result<ComponentTree, NashError> {
+    let tokens = lexer::lex(content)
         .collect::<Result<Vec<_>, _>>()
         .map_err(|err| {
+            if !print_errors {
+                return err;
+            }
             eprintln!("Error parsing script:");
             eprintln!("{}", err.message);
             if let Some(position) = err.position {
@@ -38,62 +145,66 @@ pub fn execute<R: Read, E: Executor>(
         })?;
 
     let component_tree = components::parse(tokens.iter()).map_err(|err| {
+        if !print_errors {
+            return err;
+        }
         eprintln!("Error parsing script:");
         eprintln!(
             "{}",
-            format_error(&err, &content).expect("Unable to write error information")
+            format_error(&err, content).expect("Unable to write error information")
         );
         return err;
     })?;
 
-    component_tree.post_process()?;
-
-    let result = component_tree.execute(executor).map_err(|err| {
-        eprintln!("Error executing script: {err}");
-        if let Some(call_stack) = &err.call_stack {
-            let formatted_stack = call_stack
-                .into_iter()
-                .fold("@root".to_owned(), |a, b| format!("{b}\n{a}"));
-            eprintln!("Call stack: \n{formatted_stack}");
-        }
+    Ok(component_tree)
+}
 
+fn print_execution_error(err: ExecutionError, print_errors: bool) -> ExecutionError {
+    if !print_errors {
         return err;
-    })?;
+    }
 
-    return Ok(result);
+    eprintln!("Error executing script: {err}");
+    if let Some(call_stack) = &err.call_stack {
+        let formatted_stack = call_stack
+            .iter()
+            .fold("@root".to_owned(), |a, b| format!("{b}\n{a}"));
+        eprintln!("Call stack: \n{formatted_stack}");
+    }
+
+    err
 }
 
 fn format_error(error: &ParserError, source_file: &str) -> Result<String, Error> {
     let mut result = String::new();
 
-    if let Some(start) = &error.start {
-        if let Some(end) = &error.end {
-            writeln!(
-                result,
-                "Unexpected token: {:} at index {:}",
-                error.token, start
-            )?;
-
-            let mut line = String::new();
-            let mut underline_start = 0;
-
-            for (index, char) in source_file.chars().enumerate() {
-                if char == '\n' {
-                    if index >= *end {
-                        break;
-                    }
-                    line = String::new();
-                    underline_start = start - index - 1;
-                    continue;
-                }
-
-                line += &String::from(char);
-            }
-
-            let underline = " ".repeat(underline_start) + &"^".repeat(end - start);
-            writeln!(result, "{line}")?;
-            writeln!(result, "{underline}")?;
-        }
+    if let (Some(start), Some(end)) = (error.start, error.end) {
+        let (line_number, column) = line_column(source_file, start);
+        writeln!(
+            result,
+            "Unexpected token: {:} at line {line_number}, col {column}",
+            error.token
+        )?;
+
+        // Tokens can span multiple lines (e.g. unterminated strings), so only the line the
+        // token starts on is shown
+        let line = source_file.lines().nth(line_number - 1).unwrap_or_default();
+        let underline_start = column - 1;
+        let token_length = source_file
+            .get(start..end)
+            .map(|token| token.chars().count())
+            .unwrap_or_default();
+        let underline_length = token_length
+            .min(line.chars().count().saturating_sub(underline_start))
+            .max(1);
+
+        // Shift the underline past the line number so it still lines up with the token
+        let width = line_number_width(source_file.lines().count());
+        let line = number_line(line_number, width, line);
+        let underline_start = underline_start + width + " | ".len();
+        let underline = " ".repeat(underline_start) + &"^".repeat(underline_length);
+        writeln!(result, "{line}")?;
+        writeln!(result, "{underline}")?;
     }
 
     writeln!(result, "{}", error.message)?;
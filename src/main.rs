@@ -1,6 +1,10 @@
 use std::{env, error::Error, fs::File, path::PathBuf, process::ExitCode};
 
-use nash::ExecutorOptions;
+use nash::{ExecutorOptions, NashError, ParserError};
+
+const JSON_ERRORS_FLAG: &str = "--json-errors";
+const PROFILE_FLAG: &str = "--profile";
+const CHECK_FLAG: &str = "--check";
 
 fn main() -> ExitCode {
     match main_impl() {
@@ -11,19 +15,59 @@ fn main() -> ExitCode {
 }
 
 fn main_impl() -> Result<u8, u8> {
-    let args = get_args().map_err(|err| {
-        eprintln!("Invalid arguments: {err}");
+    let args = get_args();
+    let print_error = |message: String| {
+        if args.json_errors {
+            eprintln!("{}", NashError::from(message).to_json());
+        } else {
+            eprintln!("{message}");
+        }
+    };
+
+    let file_path = args.file_path().map_err(|err| {
+        print_error(format!("Invalid arguments: {err}"));
         100
     })?;
 
-    let mut file = File::open(args.file_path).map_err(|err| {
-        eprintln!("Error reading file path: {err}");
+    let mut file = File::open(file_path).map_err(|err| {
+        print_error(format!("Error reading file path: {err}"));
         100
     })?;
 
-    let mut executor = nash::SystemExecutor::new(ExecutorOptions::default());
+    if args.check {
+        let Err(errors) = nash::check(&mut file) else {
+            return Ok(0);
+        };
+
+        for err in &errors {
+            if args.json_errors {
+                eprintln!("{}", err.to_json());
+            } else if let NashError::ParserError(ParserError {
+                line: Some(line),
+                column: Some(column),
+                ..
+            }) = err
+            {
+                eprintln!("Error checking nash script: {err} at line {line}, col {column}");
+            } else {
+                eprintln!("Error checking nash script: {err}");
+            }
+        }
+        return Err(errors.first().map(NashError::exit_code).unwrap_or(100));
+    }
+
+    let options = ExecutorOptions::builder()
+        .json_errors(args.json_errors)
+        .profile(args.profile)
+        .build();
+
+    let mut executor = nash::SystemExecutor::new(options).with_args(args.script_args.clone());
     let result = nash::execute(&mut file, &mut executor).map_err(|err| {
-        eprintln!("Error running nash script: {err}");
+        if args.json_errors {
+            eprintln!("{}", err.to_json());
+        } else {
+            eprintln!("Error running nash script: {err}");
+        }
         err.exit_code()
     })?;
 
@@ -31,13 +75,54 @@ fn main_impl() -> Result<u8, u8> {
 }
 
 struct Arguments {
-    file_path: PathBuf,
+    file_path: Option<PathBuf>,
+    script_args: Vec<String>,
+    json_errors: bool,
+    profile: bool,
+    check: bool,
+    unknown_flag: Option<String>,
+}
+
+impl Arguments {
+    /// Errors if the arguments were invalid, this is checked separately from parsing them so
+    /// that the error can be printed as json when --json-errors was passed.
+    fn file_path(&self) -> Result<&PathBuf, Box<dyn Error>> {
+        if let Some(flag) = &self.unknown_flag {
+            return Err(format!("Unknown flag {flag}").into());
+        }
+
+        self.file_path
+            .as_ref()
+            .ok_or_else(|| "First argument must be path to script".into())
+    }
 }
 
-fn get_args() -> Result<Arguments, Box<dyn Error>> {
-    let args = env::args().into_iter().collect::<Vec<_>>();
-    let file = args.get(1).ok_or("First argument must be path to script")?;
-    return Ok(Arguments {
-        file_path: PathBuf::from(file),
-    });
+fn get_args() -> Arguments {
+    let mut args = env::args().skip(1).peekable();
+
+    // Flags for nash itself must come before the script path, anything after it is passed
+    // through to the script
+    let mut json_errors = false;
+    let mut profile = false;
+    let mut check = false;
+    let mut unknown_flag = None;
+    while let Some(arg) = args.next_if(|arg| arg.starts_with("--")) {
+        match arg.as_str() {
+            JSON_ERRORS_FLAG => json_errors = true,
+            PROFILE_FLAG => profile = true,
+            CHECK_FLAG => check = true,
+            _ => {
+                unknown_flag.get_or_insert(arg);
+            }
+        }
+    }
+
+    Arguments {
+        file_path: args.next().map(PathBuf::from),
+        script_args: args.collect(),
+        json_errors,
+        profile,
+        check,
+        unknown_flag,
+    }
 }
@@ -21,3 +21,43 @@ pub fn fmt_collection<I: Iterator<Item = F>, F: Display>(
 
     Ok(())
 }
+
+/// Prefixes each line with its 1-based line number, right aligned so that the lines stay in
+/// the same column past line 9. A trailing newline on the input is kept.
+pub fn number_lines(value: &str) -> String {
+    let lines = value.lines().collect::<Vec<_>>();
+    let width = line_number_width(lines.len());
+
+    let mut result = lines
+        .into_iter()
+        .enumerate()
+        .map(|(index, line)| number_line(index + 1, width, line))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if value.ends_with('\n') {
+        result.push('\n');
+    }
+
+    result
+}
+
+pub fn number_line(number: usize, width: usize, line: &str) -> String {
+    format!("{number:>width$} | {line}")
+}
+
+/// Converts a byte offset into a 1-based line and column, columns are counted in characters so
+/// that they match what is shown in an editor.
+pub fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = source.get(..offset).unwrap_or(source);
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+    let column = before[line_start..].chars().count() + 1;
+
+    (line, column)
+}
+
+/// The number of columns needed to fit every line number up to `line_count`.
+pub fn line_number_width(line_count: usize) -> usize {
+    line_count.max(1).to_string().len()
+}
@@ -12,6 +12,7 @@ mod tests {
         stdout: <Self as Executor>::Stdout,
         stderr: <Self as Executor>::Stderr,
         options: ExecutorOptions,
+        profile: ExecutionProfile,
         pipeline_executor: P,
     }
 
@@ -24,6 +25,7 @@ mod tests {
                 stdout: Vec::new(),
                 stderr: Vec::new(),
                 options: ExecutorOptions::default(),
+                profile: ExecutionProfile::default(),
                 pipeline_executor,
             }
         }
@@ -53,10 +55,54 @@ mod tests {
         fn options(&self) -> &ExecutorOptions {
             &self.options
         }
+
+        fn profile(&mut self) -> &mut ExecutionProfile {
+            &mut self.profile
+        }
+
+        fn list_dir(&self, path: &str) -> io::Result<Vec<String>> {
+            self.pipeline_executor.list_dir(path)
+        }
+
+        fn is_dir(&self, path: &str) -> bool {
+            self.pipeline_executor.is_dir(path)
+        }
+
+        fn is_file(&self, path: &str) -> bool {
+            self.pipeline_executor.is_file(path)
+        }
+
+        fn get_env(&self, name: &str) -> Option<String> {
+            self.pipeline_executor.get_env(name)
+        }
+
+        fn set_env(&mut self, name: &str, value: &str) {
+            self.pipeline_executor.set_env(name, value)
+        }
+
+        fn read_file(&self, path: &str) -> io::Result<String> {
+            self.pipeline_executor.read_file(path)
+        }
+
+        fn write_file(&mut self, path: &str, contents: &str) -> io::Result<()> {
+            self.pipeline_executor.write_file(path, contents)
+        }
+
+        fn args(&self) -> Vec<String> {
+            self.pipeline_executor.args()
+        }
     }
 
     trait PipelineExecutor {
         fn run_pipeline(&self, pipeline: &Pipeline) -> io::Result<PipelineOutput>;
+        fn list_dir(&self, path: &str) -> io::Result<Vec<String>>;
+        fn is_dir(&self, path: &str) -> bool;
+        fn is_file(&self, path: &str) -> bool;
+        fn get_env(&self, name: &str) -> Option<String>;
+        fn set_env(&self, name: &str, value: &str);
+        fn read_file(&self, path: &str) -> io::Result<String>;
+        fn write_file(&self, path: &str, contents: &str) -> io::Result<()>;
+        fn args(&self) -> Vec<String>;
     }
 
     mock! {
@@ -64,6 +110,14 @@ mod tests {
 
         impl PipelineExecutor for PipelineExecutor {
             fn run_pipeline(&self, pipeline: &Pipeline) -> io::Result<PipelineOutput>;
+            fn list_dir(&self, path: &str) -> io::Result<Vec<String>>;
+            fn is_dir(&self, path: &str) -> bool;
+            fn is_file(&self, path: &str) -> bool;
+            fn get_env(&self, name: &str) -> Option<String>;
+            fn set_env(&self, name: &str, value: &str);
+            fn read_file(&self, path: &str) -> io::Result<String>;
+            fn write_file(&self, path: &str, contents: &str) -> io::Result<()>;
+            fn args(&self) -> Vec<String>;
         }
     }
 
@@ -1012,4 +1066,3009 @@ out((exit_code, stderr, exit_code_2, stderr_2).fmt());
         }
         "#
     );
+
+    nash_test!(
+        should_be_able_to_append_to_string_in_loop,
+        r#"
+        var mut result = "";
+        for piece in ["a", "b", "c"] {
+            result ++= piece;
+        };
+        out(result);
+        "#
+    );
+
+    nash_test!(
+        should_be_able_to_append_to_mutable_array_in_loop,
+        r#"
+        var array = mut [0];
+        for value in [1, 2, 3] {
+            array ++= value * 2;
+        };
+        out(array.fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_appending_value_of_wrong_type,
+        r#"
+        var array = mut ["test"];
+        array ++= 123;
+        "#
+    );
+
+    nash_test!(
+        should_error_when_appending_to_immutable_array,
+        r#"
+        var array = ["test"];
+        array ++= "value";
+        "#
+    );
+
+    nash_test!(
+        should_error_when_appending_to_non_mutable_string,
+        r#"
+        var value = "test";
+        value ++= "value";
+        "#
+    );
+
+    nash_test!(
+        should_be_able_to_create_and_read_maps,
+        r#"
+        var map = {"one": 1, "two": 2, "three": 3,};
+        out(map.fmt());
+        out(map.get("two").fmt());
+        out((map.has("one"), map.has("four"), map.len()).fmt());
+        out(map.keys().fmt());
+        "#
+    );
+
+    nash_test!(
+        should_be_able_to_mutate_mutable_maps,
+        r#"
+        var map = mut {"key": "value"};
+        map.set("other", "thing");
+        map.set("key", "changed");
+        out(map.remove("other"));
+        out(map.fmt());
+        "#
+    );
+
+    nash_test!(
+        should_not_be_able_to_mutate_immutable_map,
+        r#"
+        var map = {"key": "value"};
+        map.set("other", "thing");
+        "#
+    );
+
+    nash_test!(
+        should_error_when_getting_missing_key_from_map,
+        r#"
+        var map = {"key": "value"};
+        map.get("missing");
+        "#
+    );
+
+    nash_test!(
+        should_error_when_creating_map_with_inconsistent_types,
+        r#"
+        var map = {"key": "value", "other": 123};
+        "#
+    );
+
+    nash_test!(
+        should_create_empty_maps,
+        r#"
+        var map = mut {: integer};
+        out(map.fmt());
+        map.set("a", 1);
+        out(map.fmt());
+        out({: [string]}.len().fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_map_literal_has_duplicate_keys,
+        r#"
+        var map = {"a": 1, "a": 2};
+        "#
+    );
+
+    nash_test!(
+        should_error_when_map_has_duplicate_keys_at_runtime,
+        r#"
+        var key = "a";
+        var map = {key: 1, "a": 2};
+        "#
+    );
+
+    nash_test!(
+        should_still_parse_blocks_when_maps_are_supported,
+        r#"
+        {
+            out("in a block");
+        };
+        "#
+    );
+
+    nash_test!(
+        should_be_able_to_pass_maps_to_functions,
+        r#"
+        func add_entry(map: mut {integer}) {
+            map.set("added", 42);
+        }
+
+        var map = mut {"existing": 1};
+        add_entry(map);
+        out(map.fmt());
+        "#
+    );
+
+    nash_test!(
+        should_be_able_to_return_early_from_void_function,
+        r#"
+        func print_if_non_zero(value: integer) {
+            if value == 0 {
+                return;
+            };
+            out(value.fmt());
+        }
+
+        print_if_non_zero(1);
+        print_if_non_zero(0);
+        print_if_non_zero(2);
+        "#
+    );
+
+    nash_test!(
+        should_error_when_returning_nothing_from_non_void_function,
+        r#"
+        func test(): string {
+            return;
+        }
+        "#
+    );
+
+    nash_test!(
+        should_support_compound_assignment_operators,
+        r#"
+        var mut value = 10;
+        value += 5;
+        out(value.fmt());
+        value -= 3;
+        out(value.fmt());
+        value *= 4;
+        out(value.fmt());
+        value /= 6;
+        out(value.fmt());
+        value %= 5;
+        out(value.fmt());
+
+        var mut string = "hello";
+        string += " world";
+        out(string);
+        "#
+    );
+
+    nash_test!(
+        should_support_compound_assignment_in_loops,
+        r#"
+        var mut index = 0;
+        while index < 3 {
+            out(index.fmt());
+            index += 1;
+        };
+        "#
+    );
+
+    nash_test!(
+        should_fail_compound_assignment_to_non_mutable_variable,
+        r#"
+        var value = 1;
+        value += 1;
+        "#
+    );
+
+    nash_test!(
+        should_fail_compound_assignment_with_mismatched_types,
+        r#"
+        var mut value = 1;
+        value += "test";
+        "#
+    );
+
+    nash_test!(
+        should_compare_comparable_values,
+        r#"
+        out((compare(1, 2), compare(2, 2), compare(3, 2)).fmt());
+        out((compare("a", "b"), compare("b", "b"), compare("c", "b")).fmt());
+        out((compare(false, true), compare(true, true), compare(true, false)).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_comparing_values_of_different_types,
+        r#"
+        compare(1, "1");
+        "#
+    );
+
+    nash_test!(
+        should_error_when_comparing_values_that_are_not_comparable,
+        r#"
+        compare([1], [2]);
+        "#
+    );
+
+    nash_test!(
+        should_split_strings,
+        r#"
+        out("a,b,,c".split(",").fmt());
+        out("abc".split("").fmt());
+        out("no separator".split(",").fmt());
+        "#
+    );
+
+    nash_test!(
+        should_trim_strings,
+        r#"
+        out("  	  padded string   ".trim().fmt());
+        "#
+    );
+
+    nash_test!(
+        should_replace_in_strings,
+        r#"
+        out("one two one".replace("one", "three"));
+        "#
+    );
+
+    nash_test!(
+        should_change_case_of_strings,
+        r#"
+        out("Mixed Case".to_upper());
+        out("Mixed Case".to_lower());
+        "#
+    );
+
+    nash_test!(
+        should_url_encode_and_decode_strings,
+        r#"
+        var encoded = url_encode("hello world/?a=1&b=ü~");
+        out(encoded);
+        out(url_decode(encoded));
+        "#
+    );
+
+    nash_test!(
+        should_error_when_url_decoding_malformed_string,
+        r#"
+        url_decode("bad%2");
+        "#
+    );
+
+    nash_test!(
+        should_check_start_and_contents_of_strings,
+        r#"
+        var value = "hello world";
+        out((value.starts_with("hello"), value.starts_with("world")).fmt());
+        out((value.contains("o w"), value.contains("planet")).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_find_byte_index_of_substring,
+        r#"
+        var value = "hello world";
+        out((value.index_of("world"), value.index_of("planet")).fmt());
+        out("héllo".index_of("l").fmt());
+        "#
+    );
+
+    #[test]
+    fn should_keep_declarations_in_session_until_reset() {
+        let mut mock_executor = MockExecutor::new("", MockPipelineExecutor::new());
+        let mut session = Session::new();
+
+        let mut run = |script: &str, session: &mut Session| {
+            nash::execute_in_session(&mut script.as_bytes(), session, &mut mock_executor).err()
+        };
+
+        let errors = vec![
+            run(
+                r#"var greeting = "hello"; func greet(name: string) { out(name + " "); }"#,
+                &mut session,
+            ),
+            run(r#"greet("world"); out(greeting);"#, &mut session),
+            {
+                session.reset();
+                run(r#"out(greeting);"#, &mut session)
+            },
+            run(r#"greet("world");"#, &mut session),
+        ];
+
+        let stdout = str::from_utf8(mock_executor.stdout()).unwrap().to_owned();
+        assert_yaml_snapshot!((stdout, errors));
+    }
+
+    #[test]
+    fn should_not_keep_declarations_from_script_that_fails_type_checking() {
+        let mut mock_executor = MockExecutor::new("", MockPipelineExecutor::new());
+        let mut session = Session::new();
+
+        let mut run = |script: &str, session: &mut Session| {
+            nash::execute_in_session(&mut script.as_bytes(), session, &mut mock_executor).err()
+        };
+
+        let failing = r#"func greet() { out("hello"); } out(1);"#;
+        let errors = vec![
+            run(failing, &mut session),
+            run(r#"func greet() { out("hi"); } greet();"#, &mut session),
+        ];
+
+        let stdout = str::from_utf8(mock_executor.stdout()).unwrap().to_owned();
+        assert_yaml_snapshot!((stdout, errors));
+    }
+
+    #[test]
+    fn should_not_keep_declarations_from_script_that_fails_to_run() {
+        let mut mock_executor = MockExecutor::new("", MockPipelineExecutor::new());
+        let mut session = Session::new();
+
+        let mut run = |script: &str, session: &mut Session| {
+            nash::execute_in_session(&mut script.as_bytes(), session, &mut mock_executor).err()
+        };
+
+        let errors = vec![
+            run(
+                r#"func greet() { out("hello"); } var y = [1][3];"#,
+                &mut session,
+            ),
+            run("out(y.fmt());", &mut session),
+            run(r#"func greet() { out("hi"); } greet();"#, &mut session),
+        ];
+
+        let stdout = str::from_utf8(mock_executor.stdout()).unwrap().to_owned();
+        assert_yaml_snapshot!((stdout, errors));
+    }
+
+    #[test]
+    fn should_call_custom_builtin_registered_in_session() {
+        let mut mock_executor = MockExecutor::new("", MockPipelineExecutor::new());
+        let mut session = Session::new();
+        session.register_builtin(
+            "shout",
+            vec![Type::String, Type::Integer],
+            Type::String,
+            Box::new(|args| {
+                let [Value::String(value), Value::Integer(count)] = args else {
+                    return Err("Unexpected arguments".into());
+                };
+                Ok(Value::String(
+                    value.to_uppercase() + &"!".repeat(*count as usize),
+                ))
+            }),
+        );
+
+        let script = r#"out(shout("hello", 3));"#;
+        let result =
+            nash::execute_in_session(&mut script.as_bytes(), &mut session, &mut mock_executor);
+        assert!(result.is_ok());
+
+        session.reset();
+        let script = r#"out(shout("again", 1));"#;
+        let result =
+            nash::execute_in_session(&mut script.as_bytes(), &mut session, &mut mock_executor);
+        assert!(result.is_ok());
+
+        let stdout = str::from_utf8(mock_executor.stdout()).unwrap().to_owned();
+        assert_eq!(stdout, "HELLO!!!\nAGAIN!\n");
+    }
+
+    #[test]
+    fn should_type_check_custom_builtin_arguments() {
+        let mut mock_executor = MockExecutor::new("", MockPipelineExecutor::new());
+        let mut session = Session::new();
+        session.register_builtin(
+            "shout",
+            vec![Type::String],
+            Type::String,
+            Box::new(|_| panic!("Should not be called")),
+        );
+
+        let script = r#"out(shout(1));"#;
+        let Err(err) =
+            nash::execute_in_session(&mut script.as_bytes(), &mut session, &mut mock_executor)
+        else {
+            panic!("Script should fail type checking");
+        };
+        assert_eq!(err.to_string(), "Arguments are not correct");
+    }
+
+    #[test]
+    fn should_list_function_signatures_in_session() {
+        let mut mock_executor = MockExecutor::new("", MockPipelineExecutor::new());
+        let mut session = Session::new();
+
+        let script = r#"
+        func greet(name: string) { out(name); }
+        func add(left: integer, right: integer): integer { return left + right; }
+        "#;
+        let result =
+            nash::execute_in_session(&mut script.as_bytes(), &mut session, &mut mock_executor);
+        assert!(result.is_ok());
+
+        assert_eq!(
+            session.functions(),
+            vec![
+                "func add(integer, integer): integer",
+                "func greet(string): void",
+            ]
+        );
+    }
+
+    nash_test!(
+        should_map_filter_and_reduce_arrays_with_functions,
+        r#"
+        func double(value: integer): integer {
+            return value * 2;
+        }
+
+        func is_big(value: integer): boolean {
+            return value > 4;
+        }
+
+        func sum(total: integer, value: integer): integer {
+            return total + value;
+        }
+
+        func describe(value: integer): string {
+            return "<" + value.fmt() + ">";
+        }
+
+        var values = [1, 2, 3, 4];
+        out(values.map(double).fmt());
+        out(values.map(double).filter(is_big).fmt());
+        out(values.reduce(sum, 10).fmt());
+        out(values.map(describe).fmt());
+        out(double.fmt());
+        "#
+    );
+
+    nash_test!(
+        should_not_map_with_function_of_wrong_type,
+        r#"
+        func shout(value: string): string {
+            return value.to_upper();
+        }
+
+        out([1, 2, 3].map(shout).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_format_values_with_spec,
+        r#"
+        out("[" + 42.fmt("05") + "]");
+        out("[" + 42.fmt("5") + "]");
+        out("[" + 42.fmt("<5") + "]");
+        out("[" + "abc".fmt("7") + "]");
+        out("[" + "abc".fmt(">7") + "]");
+        out("[" + 123456.fmt("3") + "]");
+        "#
+    );
+
+    nash_test!(
+        should_error_if_format_spec_is_invalid,
+        r#"
+        out(42.fmt("5x"));
+        "#
+    );
+
+    nash_test!(
+        should_support_recursive_functions,
+        r#"
+        func fib(n: integer): integer {
+            if n < 2 {
+                return n;
+            };
+            return fib(n - 1) + fib(n - 2);
+        }
+
+        out(fib(10).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_support_mutually_recursive_functions,
+        r#"
+        func is_even(n: integer): boolean {
+            if n == 0 {
+                return true;
+            };
+            return is_odd(n - 1);
+        }
+
+        func is_odd(n: integer): boolean {
+            if n == 0 {
+                return false;
+            };
+            return is_even(n - 1);
+        }
+
+        out((is_even(10), is_odd(7), is_even(3)).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_if_recursion_exceeds_call_stack_depth,
+        r#"
+        func forever(n: integer): integer {
+            return forever(n + 1);
+        }
+
+        forever(0);
+        "#
+    );
+
+    nash_test!(
+        should_run_command_with_input,
+        r#"
+        var result = `tr a-z A-Z`.run_with_input("hello");
+        out(result);
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_run_pipeline()
+                .with(predicate::eq::<Pipeline>(Pipeline::new(
+                    vec![CommandDefinition::new(
+                        "tr".to_owned(),
+                        vec!["a-z".to_owned(), "A-Z".to_owned()],
+                        false,
+                    )],
+                    Some(PipelineSource::Literal("hello\n".to_owned())),
+                    None,
+                )))
+                .return_once(|_| Ok(pipeline_success("HELLO", 1)))
+                .once();
+        }
+    );
+
+    nash_test!(
+        should_match_on_values,
+        r#"
+        for value in [1, 2, 3] {
+            match value {
+                1 => {
+                    out("one");
+                },
+                2 => {
+                    out("two");
+                },
+                _ => {
+                    out("many");
+                },
+            };
+        };
+
+        var name = match "b" { "a" => "first", "b" => "second", _ => "other" };
+        out(name);
+
+        out(match 1 == 1 { true => "yes", false => "no" });
+        "#
+    );
+
+    nash_test!(
+        should_error_if_match_is_not_exhaustive,
+        r#"
+        match 3 {
+            1 => {},
+            2 => {},
+        };
+        "#
+    );
+
+    nash_test!(
+        should_error_if_match_arms_have_different_types,
+        r#"
+        var value = match 3 { 1 => "one", _ => 2 };
+        "#
+    );
+
+    nash_test!(
+        should_execute_chained_else_if_conditions,
+        r#"
+        for value in [1, 2, 3, 4] {
+            if value == 1 {
+                out("one");
+            } else if value == 2 {
+                out("two");
+            } else if value == 3 {
+                out("three");
+            } else {
+                out("other");
+            };
+        };
+        "#
+    );
+
+    nash_test!(
+        should_support_bitwise_operators,
+        r#"
+        out((12 & 10, 12 | 10, 12 ^ 10, 1 << 4, 64 >> 2).fmt());
+        out(((0 - 8) >> 1, (0 - 1) >> 40, 1 << 40).fmt());
+        out((1 | 2 | 4, 1 <= 2, 3 >= 4).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_shifting_by_negative_amount,
+        r#"
+        var amount = 0 - 1;
+        out((1 << amount).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_use_final_expression_without_semicolon_as_block_value,
+        r#"
+        var value = {
+            var base = 20;
+            base + 22
+        };
+        out(value.fmt());
+
+        var nested = { { "inner" } };
+        out(nested);
+        "#
+    );
+
+    nash_test!(
+        should_use_block_value_in_expressions,
+        r#"
+        var x = { var t = 1; t + 1 };
+        out(x.fmt());
+        out(({ var t = 2; t * 3 } + 1).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_not_use_final_expression_with_semicolon_as_block_value,
+        r#"
+        var value = {
+            42;
+        };
+        "#
+    );
+
+    nash_test!(
+        should_list_directory_entries,
+        r#"
+        out(list_dir("some/dir").fmt());
+        out(list_dir("some/dir", true).fmt());
+        out((is_dir("some/dir"), is_file("some/dir")).fmt());
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_list_dir()
+                .with(predicate::eq("some/dir"))
+                .returning(|_| Ok(vec!["b.txt".to_owned(), "a".to_owned()]))
+                .times(2);
+            executor
+                .expect_is_dir()
+                .with(predicate::eq("some/dir"))
+                .return_const(true);
+            executor
+                .expect_is_file()
+                .with(predicate::eq("some/dir"))
+                .return_const(false);
+        }
+    );
+
+    nash_test!(
+        should_error_if_directory_can_not_be_listed,
+        r#"
+        list_dir("missing");
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_list_dir()
+                .returning(|_| Err(io::Error::new(io::ErrorKind::NotFound, "not found")));
+        }
+    );
+
+    nash_test!(
+        should_negate_integers,
+        r#"
+        var value = 4;
+        out((-3).fmt());
+        out((-(1 + 2), -value + 10, 1 - -value, --5, -value.fmt().len()).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_parse_negative_number_literals,
+        r#"
+        var value = 4;
+        out((-2147483648).fmt());
+        out([-1, - 2, 3 -1, value -1].fmt());
+        out(-12.fmt());
+        out((-1.5).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_negative_literal_is_out_of_range,
+        r#"
+        out((-2147483649).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_not_negate_strings,
+        r#"
+        out((-"text").fmt());
+        "#
+    );
+
+    nash_test!(
+        should_partition_arrays_with_function,
+        r#"
+        func is_even(value: integer): boolean {
+            return (value % 2) == 0;
+        }
+
+        func is_positive(value: integer): boolean {
+            return value > 0;
+        }
+
+        out([1, 2, 3, 4, 5].partition(is_even).fmt());
+        out([1, 2, 3].partition(is_positive).fmt());
+        out([-1, -2].partition(is_positive).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_invert_booleans,
+        r#"
+        var condition = false;
+        if !condition {
+            out("inverted");
+        };
+        out((!true, !true == false, !!true, !(1 == 2)).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_not_invert_integers,
+        r#"
+        out((!1).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_parse_comparison_operators_next_to_pipeline_arrows,
+        r#"
+        var a = 2;
+        var b = 1;
+        out((a >= b, a <= b, a>=b, a<=b, a > b, a < b).fmt());
+        exec "input" => `command`;
+        exec "input"=>`command`;
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_run_pipeline()
+                .with(predicate::eq::<Pipeline>(Pipeline::new(
+                    vec!["command".into()],
+                    Some(PipelineSource::Literal("input\n".to_owned())),
+                    None,
+                )))
+                .returning(|_| Ok(pipeline_success("", 1)))
+                .times(2);
+        }
+    );
+
+    nash_test!(
+        should_not_parse_comparison_operators_with_whitespace_between_tokens,
+        r#"
+        out((2 > = 1).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_not_parse_pipeline_arrow_with_whitespace_between_tokens,
+        r#"
+        exec "input" = > `command`;
+        "#
+    );
+
+    nash_test!(
+        should_not_parse_compound_assignment_with_whitespace_between_tokens,
+        r#"
+        var mut value = 1;
+        value + = 2;
+        "#
+    );
+
+    nash_test!(
+        should_not_parse_append_with_whitespace_between_tokens,
+        r#"
+        var mut value = "a";
+        value + + = "b";
+        "#
+    );
+
+    nash_test!(
+        should_iterate_over_ranges,
+        r#"
+        var values = [10, 20, 30];
+        for i in 0..values.len() {
+            out(values[i].fmt());
+        };
+        out((0..5).fmt());
+        out(((5..5), (5..0), (-2..1)).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_count_lines_and_words,
+        r#"
+        var output = exec `command`;
+        out((output.line_count(), output.word_count()).fmt());
+        out(("".line_count(), "  lots   of   spaces  ".word_count(), "no newline".line_count()).fmt());
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_run_pipeline()
+                .returning(|_| Ok(pipeline_success("first  line\n\nthird line\n", 1)));
+        }
+    );
+
+    nash_test!(
+        should_read_and_set_environment_variables,
+        r#"
+        out(env("HOME"));
+        set_env("GREETING", "hello");
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_get_env()
+                .with(predicate::eq("HOME"))
+                .return_const(Some("/home/user".to_owned()));
+            executor
+                .expect_set_env()
+                .with(predicate::eq("GREETING"), predicate::eq("hello"))
+                .return_const(())
+                .once();
+        }
+    );
+
+    nash_test!(
+        should_error_if_environment_variable_is_not_set,
+        r#"
+        out(env("MISSING"));
+        "#,
+        "",
+        |executor| {
+            executor.expect_get_env().return_const(None);
+        }
+    );
+
+    nash_test!(
+        should_read_script_arguments,
+        r#"
+        for arg in args() {
+            out(arg);
+        };
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_args()
+                .return_const(vec!["first".to_owned(), "second".to_owned()]);
+        }
+    );
+
+    nash_test!(
+        should_return_empty_array_when_no_script_arguments,
+        r#"
+        out(args().len().fmt());
+        "#,
+        "",
+        |executor| {
+            executor.expect_args().return_const(Vec::new());
+        }
+    );
+
+    nash_test!(
+        should_check_if_values_are_empty,
+        r#"
+        out("".is_empty().fmt());
+        out("value".is_empty().fmt());
+        var array = mut ["value"];
+        array.pop();
+        out(array.is_empty().fmt());
+        out([1, 2].is_empty().fmt());
+        out(().is_empty().fmt());
+        out((1, "two").is_empty().fmt());
+        "#
+    );
+
+    nash_test!(
+        should_catch_failing_command,
+        r#"
+        try {
+            exec `my_command`;
+            out("unreachable");
+        } catch error {
+            out("caught: " + error);
+        };
+        out("done");
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_run_pipeline()
+                .with(predicate::eq::<Pipeline>(["my_command"].into()))
+                .return_once(|_| Ok(PipelineOutput::new(None, Some(1.into()))))
+                .once();
+        }
+    );
+
+    nash_test!(
+        should_catch_errors_raised_inside_functions,
+        r#"
+        func fail() {
+            exec `my_command`;
+        }
+        try {
+            fail();
+        } catch error {
+            out(error);
+        };
+        exec `my_command`;
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_run_pipeline()
+                .with(predicate::eq::<Pipeline>(["my_command"].into()))
+                .returning(|_| Ok(PipelineOutput::new(None, Some(2.into()))))
+                .times(2);
+        }
+    );
+
+    nash_test!(
+        should_not_catch_control_flow,
+        r#"
+        for value in [1, 2, 3] {
+            try {
+                if value == 2 {
+                    break;
+                };
+                out(value.fmt());
+            } catch error {
+                out(error);
+            };
+        };
+        try {
+            exit 3;
+        } catch error {
+            out(error);
+        };
+        "#
+    );
+
+    nash_test!(
+        should_include_nested_functions_in_error_call_stack,
+        r#"
+        func inner() {
+            exec `my_command`;
+        }
+        func outer() {
+            inner();
+        }
+        outer();
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_run_pipeline()
+                .with(predicate::eq::<Pipeline>(["my_command"].into()))
+                .return_once(|_| Ok(PipelineOutput::new(None, Some(1.into()))))
+                .once();
+        }
+    );
+
+    nash_test!(
+        should_round_trip_json,
+        r#"
+        var data = parse_json("{ \"name\": \"nash \\\"shell\\\"\", \"tags\": [\"a\", \"b\"], \"meta\": { \"size\": 42, \"enabled\": true, \"parent\": null } }");
+        out(to_json(data));
+
+        var fields = data.as_map();
+        out(fields.get("name").as_string());
+        var meta = fields.get("meta").as_map();
+        out((meta.get("size").as_integer() + 1).fmt());
+        out(meta.get("enabled").as_boolean().fmt());
+        out(meta.get("parent").is_null().fmt());
+        for tag in fields.get("tags").as_array() {
+            out(tag.as_string());
+        };
+        "#
+    );
+
+    nash_test!(
+        should_convert_values_to_json,
+        r#"
+        out(to_json({ "first": [1, 2], "second": [3] }));
+        out(to_json(("a\\b", false)));
+        out(to_json(['a', '"']));
+        "#
+    );
+
+    nash_test!(
+        should_allow_mixed_json_values_in_collections,
+        r#"
+        var values = mut [parse_json("1"), parse_json("\"x\"")];
+        values.push(parse_json("[true]"));
+        values.insert(0, parse_json("2.5"));
+        out(to_json(values));
+
+        var fields = mut { "a": parse_json("1"), "b": parse_json("\"x\"") };
+        fields.set("a", parse_json("false"));
+        out(to_json(fields.get("a")));
+        out(to_json(fields.get("b")));
+        "#
+    );
+
+    nash_test!(
+        should_parse_json_floats,
+        r#"
+        var values = parse_json("[1.5, -2e3, 7]").as_array();
+        out(values[0].as_float().fmt());
+        out(values[1].as_float().fmt());
+        out(values[2].as_integer().fmt());
+        out(to_json(values));
+        "#
+    );
+
+    nash_test!(
+        should_error_when_json_is_nested_too_deeply,
+        r#"
+        parse_json("[".repeat(100000));
+        "#
+    );
+
+    nash_test!(
+        should_error_when_json_integer_is_out_of_range,
+        r#"
+        parse_json("4294967296");
+        "#
+    );
+
+    nash_test!(
+        should_error_when_narrowing_json_to_wrong_type,
+        r#"
+        out(parse_json("[1, 2]").as_string());
+        "#
+    );
+
+    nash_test!(
+        should_parse_sizes,
+        r#"
+        out(parse_size("512").fmt());
+        out(parse_size("10k").fmt());
+        out(parse_size("10M").fmt());
+        out(parse_size("1G").fmt());
+        out(parse_size("3Ki").fmt());
+        out(parse_size("2Mi").fmt());
+        out(parse_size("5kB").fmt());
+        out(parse_size("10MB").fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_on_unknown_size_suffix,
+        r#"
+        out(parse_size("10X").fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_on_size_too_large_for_integer,
+        r#"
+        out(parse_size("2T").fmt());
+        "#
+    );
+
+    nash_test!(
+        should_read_all_remaining_input_using_read_all,
+        r#"
+var first = read();
+var rest = read_all();
+out(first.fmt());
+out(rest.fmt());
+out(rest.line_count().fmt());
+"#,
+        "first_line\nsecond_line\n\nfourth_line\n"
+    );
+
+    nash_test!(
+        should_replace_limited_number_of_occurrences,
+        r#"
+        var value = "a-b-c-d";
+        out(value.replace("-", "+"));
+        out(value.replace_first("-", "+"));
+        out(value.replace_n("-", "+", 2));
+        out(value.replace_n("-", "+", 0));
+        out(value.replace_n("-", "+", 10));
+        "#
+    );
+
+    nash_test!(
+        should_error_when_replacing_empty_string,
+        r#"
+        out("value".replace_first("", "+"));
+        "#
+    );
+
+    nash_test!(
+        should_read_and_write_files,
+        r#"
+        var contents = read_file("input.txt");
+        write_file("output.txt", contents.to_upper());
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_read_file()
+                .with(predicate::eq("input.txt"))
+                .return_once(|_| Ok("file contents".to_owned()));
+            executor
+                .expect_write_file()
+                .with(predicate::eq("output.txt"), predicate::eq("FILE CONTENTS"))
+                .return_once(|_, _| Ok(()))
+                .once();
+        }
+    );
+
+    nash_test!(
+        should_include_path_when_reading_file_fails,
+        r#"
+        out(read_file("missing.txt"));
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_read_file()
+                .return_once(|_| Err(io::Error::new(io::ErrorKind::NotFound, "No such file")));
+        }
+    );
+
+    nash_test!(
+        should_read_lines_of_file,
+        r#"
+        for (index, line) in read_lines("input.txt") {
+            out("${index}: ${line.fmt()}");
+        };
+        out(read_lines("empty.txt").len().fmt());
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_read_file()
+                .with(predicate::eq("input.txt"))
+                .return_once(|_| Ok("first\r\nsecond\n\nlast\n".to_owned()));
+            executor
+                .expect_read_file()
+                .with(predicate::eq("empty.txt"))
+                .return_once(|_| Ok("".to_owned()));
+        }
+    );
+
+    nash_test!(
+        should_read_last_line_of_file_without_trailing_newline,
+        r#"
+        out(read_lines("input.txt").fmt());
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_read_file()
+                .return_once(|_| Ok("first\r\nsecond".to_owned()));
+        }
+    );
+
+    nash_test!(
+        should_include_path_when_reading_lines_fails,
+        r#"
+        read_lines("missing.txt");
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_read_file()
+                .return_once(|_| Err(io::Error::new(io::ErrorKind::NotFound, "No such file")));
+        }
+    );
+
+    nash_test!(
+        should_get_length_of_tuple,
+        r#"
+        out((1, "two", false).len().fmt());
+        out(().len().fmt());
+        "#
+    );
+
+    nash_test!(
+        should_index_tuples,
+        r#"
+        var mixed = (1, "two", false);
+        out(mixed[1]);
+        var same = ("a", "b", "c");
+        for index in 0..same.len() {
+            out(same[index]);
+        };
+        "#
+    );
+
+    nash_test!(
+        should_error_when_indexing_tuple_out_of_bounds,
+        r#"
+        var values = ("a", "b");
+        var index = 2;
+        out(values[index]);
+        "#
+    );
+
+    nash_test!(
+        should_error_when_tuple_index_can_not_be_type_checked,
+        r#"
+        var mixed = (1, "two");
+        var index = 1;
+        out(mixed[index].fmt());
+        "#
+    );
+
+    nash_test!(
+        should_get_windows_of_array,
+        r#"
+        var values = [1, 3, 6, 10];
+        out(values.windows(3).fmt());
+        out(values.windows(4).fmt());
+        out(values.windows(5).fmt());
+        for pair in values.pairs() {
+            out((pair[1] - pair[0]).fmt());
+        };
+        "#
+    );
+
+    nash_test!(
+        should_error_on_window_size_of_zero,
+        r#"
+        out([1, 2].windows(0).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_indexing_array_with_negative_index,
+        r#"
+        var values = ["a", "b", "c"];
+        out(values[-1]);
+        "#
+    );
+
+    nash_test!(
+        should_call_function_stored_in_variable,
+        r#"
+        func double(value: integer): integer {
+            return value * 2;
+        }
+        func triple(value: integer): integer {
+            return value * 3;
+        }
+        func apply(function: func(integer): integer, value: integer): integer {
+            return function(value);
+        }
+
+        var operation = double;
+        out(operation(4).fmt());
+        out(apply(triple, 4).fmt());
+        for function in [double, triple] {
+            out(function(5).fmt());
+        };
+        "#
+    );
+
+    nash_test!(
+        should_error_when_calling_function_variable_with_wrong_arguments,
+        r#"
+        func double(value: integer): integer {
+            return value * 2;
+        }
+
+        var operation = double;
+        out(operation("4").fmt());
+        "#
+    );
+
+    nash_test!(
+        should_insert_remove_and_clear_array_elements,
+        r#"
+        var values = mut ["b", "d"];
+        values.insert(0, "a");
+        values.insert(2, "c");
+        values.insert(4, "e");
+        out(values.fmt());
+        out(values.remove(1));
+        out(values.fmt());
+        values.clear();
+        out(values.fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_removing_out_of_bounds,
+        r#"
+        var values = mut ["a"];
+        values.remove(1);
+        "#
+    );
+
+    nash_test!(
+        should_error_when_inserting_wrong_type,
+        r#"
+        var values = mut ["a"];
+        values.insert(0, 1);
+        "#
+    );
+
+    nash_test!(
+        should_error_when_removing_from_immutable_array,
+        r#"
+        var values = ["a"];
+        values.remove(0);
+        "#
+    );
+
+    nash_test!(
+        should_fail_to_clear_array_in_use,
+        r#"
+        var values = mut ["a", "b"];
+        for value in values {
+            values.clear();
+        };
+        "#
+    );
+
+    nash_test!(
+        should_format_string_with_map,
+        r#"
+        var values = {"name": "world", "greeting": "Hello"};
+        out(format_map("{greeting} {name}! {{literal}}", values));
+        out(format_map("{greeting} {missing}", values, true));
+        "#
+    );
+
+    nash_test!(
+        should_error_when_formatting_with_missing_key,
+        r#"
+        var values = {"name": "world"};
+        out(format_map("Hello {missing}", values));
+        "#
+    );
+
+    nash_test!(
+        should_search_and_reverse_arrays,
+        r#"
+        var values = mut [1, 2, 3, 2];
+        out(values.contains(2).fmt());
+        out(values.contains(5).fmt());
+        out(values.index_of(2).fmt());
+        out(values.index_of(5).fmt());
+        out(values.reverse().fmt());
+        out(values.fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_searching_array_with_wrong_type,
+        r#"
+        var values = [1, 2, 3];
+        values.contains("1");
+        "#
+    );
+
+    nash_test!(
+        should_run_math_builtins,
+        r#"
+        out(abs(-5).fmt());
+        out(abs(5).fmt());
+        out(min(3, -2).fmt());
+        out(max(3, -2).fmt());
+        out(pow(2, 10).fmt());
+        out(pow(-3, 0).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_on_negative_exponent,
+        r#"
+        pow(2, -1);
+        "#
+    );
+
+    nash_test!(
+        should_error_on_abs_overflow,
+        r#"
+        var min = -2147483647 - 1;
+        abs(min);
+        "#
+    );
+
+    nash_test!(
+        should_run_math_builtins_on_floats,
+        r#"
+        out(abs(-1.5).fmt());
+        out(min(3.5, -2.0).fmt());
+        out(max(3.5, -2.0).fmt());
+        out(pow(2.0, 0.5).fmt());
+        out(pow(4.0, -1.0).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_float_power_is_not_finite,
+        r#"
+        pow(-8.0, 0.5);
+        "#
+    );
+
+    nash_test!(
+        should_parse_floats,
+        r#"
+        out(parse_float("1").fmt());
+        out(parse_float("-2.5").fmt());
+        out(parse_float("1e3").fmt());
+        out((parse_float("0.5") == parse_float("5e-1")).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_format_floats_with_precision,
+        r#"
+        out("[" + parse_float("2.5").fmt(".2") + "]");
+        out("[" + parse_float("2.5").fmt(".0") + "]");
+        out("[" + parse_float("-1.005").fmt("8.1") + "]");
+        out("[" + parse_float("3.14159").fmt(">8.3") + "]");
+        "#
+    );
+
+    nash_test!(
+        should_error_when_using_precision_with_integer,
+        r#"
+        out(42.fmt(".2"));
+        "#
+    );
+
+    nash_test!(
+        should_error_when_parsing_float_with_trailing_characters,
+        r#"
+        parse_float("1.5abc");
+        "#
+    );
+
+    nash_test!(
+        should_error_when_parsing_infinite_float,
+        r#"
+        parse_float("inf");
+        "#
+    );
+
+    nash_test!(
+        should_error_when_parsing_nan_float,
+        r#"
+        parse_float("NaN");
+        "#
+    );
+
+    nash_test!(
+        should_support_float_literals,
+        r#"
+        var x = 1.5;
+        out(x.fmt());
+        out((x == parse_float("1.5")).fmt());
+        out(0.25.fmt());
+        out(1.fmt());
+        for i in 1..3 {
+            out(i.fmt());
+        };
+        "#
+    );
+
+    nash_test!(
+        should_do_arithmetic_with_floats,
+        r#"
+        var x = 1.5;
+        out((-x).fmt());
+        out((x + x).fmt());
+        out((x - 2.0).fmt());
+        out((x * 3.0).fmt());
+        out((x / 2.0).fmt());
+        out((7.5 % 2.0).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_compare_floats,
+        r#"
+        var x = 1.5;
+        out((x < x).fmt());
+        out((x <= x).fmt());
+        out((x > 0.5).fmt());
+        out((x >= 2.5).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_float_operation_is_not_finite,
+        r#"
+        out((1.0 / 0.0).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_mixing_floats_and_integers,
+        r#"
+        out((1.5 + 1).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_using_get_expression_on_non_tuple,
+        r#"
+        var x = 1;
+        out(x.5.fmt());
+        "#
+    );
+
+    nash_test!(
+        should_get_display_width_of_strings,
+        r#"
+        var lines = read_all().split(",");
+        var colored = lines[0];
+        var wide = lines[1];
+        out((colored.len(), colored.display_width()).fmt());
+        out((wide.len(), wide.display_width()).fmt());
+        out("é".display_width().fmt());
+        out(wide.fmt(">10") + "|");
+        out(colored.fmt("<10") + "|");
+        out(wide.fmt(">10w") + "|");
+        out(colored.fmt("<10w") + "|");
+        "#,
+        "\x1b[31mred\x1b[0m,日本"
+    );
+
+    nash_test!(
+        should_convert_values_to_plain_strings,
+        r#"
+        out("hi".fmt());
+        out("hi".str());
+        out(42.str());
+        out(true.str());
+        out(["a", "b"].str());
+        "#
+    );
+
+    nash_test!(
+        should_create_default_values_for_types,
+        r#"
+        out((default(string), default(integer), default(boolean), default(float)).fmt());
+        var values = default(mut [string]);
+        values.push("a");
+        out(values.fmt());
+        out(default({integer}).len().fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_type_has_no_default,
+        r#"
+        default(void);
+        "#
+    );
+
+    nash_test!(
+        should_repeat_strings_and_join_arrays,
+        r#"
+        out("=".repeat(10));
+        out("ab".repeat(0).fmt());
+        out(["a", "b", "c"].join(", "));
+        out(default([string]).join(", ").fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_repeating_negative_times,
+        r#"
+        "=".repeat(-1);
+        "#
+    );
+
+    nash_test!(
+        should_reject_chained_operators_before_running,
+        r#"
+        out("should not run");
+        var value = 1 + 2 * 3;
+        "#
+    );
+
+    nash_test!(
+        should_break_and_continue_labeled_loops,
+        r#"
+        outer: for i in [1, 2, 3] {
+            for j in [1, 2, 3] {
+                if j == 2 {
+                    continue outer;
+                };
+                if i == 3 {
+                    break outer;
+                };
+                out("${i.fmt()} ${j.fmt()}");
+            };
+            out("not reached");
+        };
+
+        var mut count = 0;
+        counter: while true {
+            count += 1;
+            for value in [1] {
+                if count == 3 {
+                    break counter;
+                };
+                break;
+            };
+        };
+        out(count.fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_breaking_unknown_label,
+        r#"
+        outer: for i in [1, 2] {
+            break inner;
+        };
+        "#
+    );
+
+    nash_test!(
+        should_convert_between_tuples_and_arrays,
+        r#"
+        var values = (1, 2, 3).to_array();
+        out((values.fmt(), values.len()).fmt());
+        var pair = ["a", "b"].to_tuple(2);
+        out(pair.0 + pair.1);
+        "#
+    );
+
+    nash_test!(
+        should_error_when_converting_heterogeneous_tuple_to_array,
+        r#"
+        (1, "a").to_array();
+        "#
+    );
+
+    nash_test!(
+        should_error_when_converting_array_to_tuple_of_wrong_length,
+        r#"
+        var values = ["a", "b", "c"];
+        values.to_tuple(2);
+        "#
+    );
+
+    nash_test!(
+        should_error_when_to_tuple_length_is_too_large,
+        r#"
+        [1].to_tuple(2000000000);
+        "#
+    );
+
+    nash_test!(
+        should_use_if_as_value,
+        r#"
+        var value = 5;
+        var size = if value > 10 { "big" } else if value > 3 { "medium" } else { "small" };
+        out(size);
+        if value > 3 {
+            out("statement");
+        };
+        "#
+    );
+
+    nash_test!(
+        should_require_else_when_using_if_as_value,
+        r#"
+        var size = if true { "big" };
+        "#
+    );
+
+    nash_test!(
+        should_not_require_else_when_if_value_is_unused,
+        r#"
+        var values = mut [1, 2, 3];
+        if true { values.pop() };
+        if false { 1 } else if true { "two" };
+        out(values.fmt());
+        "#
+    );
+
+    nash_test!(
+        should_require_if_branches_to_have_same_type,
+        r#"
+        var size = if true { "big" } else { 1 };
+        "#
+    );
+
+    nash_test!(
+        should_keep_line_terminator_using_read_raw,
+        r#"
+        out(read().fmt());
+        out(read_raw().fmt());
+        out(read_raw().fmt());
+        out(read_raw().fmt());
+        out(read_raw().fmt());
+        "#,
+        "first\r\nfirst\r\nsecond\nthird"
+    );
+
+    nash_test!(
+        should_inspect_bits_of_integers,
+        r#"
+        for value in [0, 10, 255, 0 - 1] {
+            out((value.to_binary(), value.to_hex(), value.count_ones(), value.leading_zeros()).fmt());
+        };
+        "#
+    );
+
+    nash_test!(
+        should_run_function_for_each_array_element,
+        r#"
+        func print(value: string) {
+            out("item: " + value);
+        }
+
+        ["a", "b", "c"].each(print);
+        "#
+    );
+
+    nash_test!(
+        should_require_each_function_to_return_void,
+        r#"
+        func double(value: integer): integer {
+            return value * 2;
+        }
+
+        [1, 2].each(double);
+        "#
+    );
+
+    nash_test!(
+        should_number_lines,
+        r#"
+        out(read_all().number_lines());
+        "#,
+        "first\nsecond\nthird\n"
+    );
+
+    nash_test!(
+        should_align_line_numbers_past_line_nine,
+        r#"
+        out(read_all().number_lines());
+        "#,
+        "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11"
+    );
+
+    nash_test!(
+        should_parse_merge_stderr_command_option,
+        r#"
+        out(exec `command1`|merge stderr| => `command2`);
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_run_pipeline()
+                .with(predicate::eq::<Pipeline>(Pipeline::new(
+                    vec![
+                        CommandDefinition::new("command1".to_owned(), Vec::new(), false)
+                            .with_merged_stderr(),
+                        CommandDefinition::new("command2".to_owned(), Vec::new(), false),
+                    ],
+                    None,
+                    None,
+                )))
+                .return_once(|_| {
+                    Ok(PipelineOutput::new(
+                        Some("merged".to_owned()),
+                        [0.into(), 0.into()],
+                    ))
+                })
+                .once();
+        }
+    );
+
+    nash_test!(
+        should_allow_merge_as_a_name,
+        r#"
+        func merge(a: integer, b: integer): integer {
+            return a | b;
+        }
+
+        var merge = merge(1, 2);
+        out((merge | merge(4, 8)).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_not_allow_capturing_and_merging_stderr,
+        r#"
+        exec `command`|cap stderr, merge stderr|;
+        "#
+    );
+
+    nash_test!(
+        should_tee_pipeline_output_to_file,
+        r#"
+        var output = exec `command1` => tee("file");
+        out(output);
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_run_pipeline()
+                .with(predicate::eq::<Pipeline>(Pipeline::new(
+                    vec!["command1".into()],
+                    None,
+                    Some(PipelineDestination::Tee("file".to_owned())),
+                )))
+                .return_once(|_| Ok(pipeline_success("from_command", 1)))
+                .once();
+        }
+    );
+
+    nash_test!(
+        should_not_allow_empty_command_literal,
+        r#"
+        var command = ``;
+        "#
+    );
+
+    nash_test!(
+        should_not_allow_whitespace_command_literal,
+        r#"
+        var command = ` `;
+        "#
+    );
+
+    nash_test!(
+        should_not_allow_blank_program_name_in_command_literal,
+        r#"
+        var command = `"  " argument`;
+        "#
+    );
+
+    nash_test!(
+        should_capture_stdout_of_each_pipeline_stage,
+        r#"
+        var result = exec `command1`|cap stdout as first_out| => `command2`;
+        out("${first_out} => ${result}");
+        first_out = "changed";
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_run_pipeline()
+                .with(predicate::eq::<Pipeline>(Pipeline::new(
+                    vec![
+                        CommandDefinition::new("command1".to_owned(), Vec::new(), false)
+                            .with_captured_stdout(),
+                        CommandDefinition::new("command2".to_owned(), Vec::new(), false),
+                    ],
+                    None,
+                    None,
+                )))
+                .return_once(|_| {
+                    Ok(PipelineOutput::new(
+                        Some("from_command_2".to_owned()),
+                        [
+                            CommandOutput::from(0).with_stdout("from_command_1".to_owned()),
+                            CommandOutput::from(0),
+                        ],
+                    ))
+                })
+                .once();
+        }
+    );
+
+    nash_test!(
+        should_pass_substituted_commands_to_pipeline,
+        r#"
+        out(exec `diff` subst(`gen_a`) subst(`gen_b "arg"`));
+        "#,
+        "",
+        |executor| {
+            executor
+                .expect_run_pipeline()
+                .with(predicate::eq::<Pipeline>(Pipeline::new(
+                    vec![CommandDefinition::from("diff")
+                        .with_substitution("gen_a".into())
+                        .with_substitution(CommandDefinition::new(
+                            "gen_b".to_owned(),
+                            vec!["arg".to_owned()],
+                            false,
+                        ))],
+                    None,
+                    None,
+                )))
+                .return_once(|_| Ok(pipeline_success("no differences", 1)))
+                .once();
+        }
+    );
+
+    nash_test!(
+        should_not_allow_substituting_non_command_values,
+        r#"
+        exec `diff` subst("not a command");
+        "#
+    );
+
+    nash_test!(
+        should_sort_integer_arrays,
+        r#"
+        var values = mut [3, -1, 10, 2];
+        out(values.sort().fmt());
+        out(values.fmt());
+        "#
+    );
+
+    nash_test!(
+        should_sort_string_arrays,
+        r#"
+        out(["pear", "apple", "Banana", "cherry"].sort().fmt());
+        "#
+    );
+
+    nash_test!(
+        should_sort_arrays_using_function,
+        r#"
+        func by_length(left: string, right: string): integer {
+            return left.len() - right.len();
+        }
+
+        out(["ccc", "a", "bb"].sort_by(by_length).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_compare_empty_arrays_using_item_type,
+        r#"
+        out((default([string]) == default([string])).fmt());
+        out((default([string]) == default([integer])).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_ignore_mutability_when_comparing_arrays,
+        r#"
+        out((mut [1, 2] == [1, 2]).fmt());
+        out((mut {"a": 1} == {"a": 1}).fmt());
+        out(([mut [1]] == [[1]]).fmt());
+        out(({"a": mut [(1, mut {"b": 2})]} == {"a": [(1, {"b": 2})]}).fmt());
+        out(([mut [1]] == [[2]]).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_slice_arrays,
+        r#"
+        var values = [1, 2, 3, 4];
+        out(values.slice(1, 3).fmt());
+        out(values.slice(0, 0).fmt());
+        out(values.slice(2, 5).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_slice_strings_by_character,
+        r#"
+        out("hello".slice(1, 3));
+        out("héllo wörld".slice(1, 8));
+        out("hello".slice(3, 1));
+        "#
+    );
+
+    nash_test!(
+        should_return_value_when_asserted_type_matches,
+        r#"
+        out(assert_type(1, "integer").fmt());
+        out(assert_type([[1], [2]], "[[integer]]").fmt());
+        out(assert_type(parse_json("[1, 2]"), "[json]").fmt());
+        out(assert_type(mut {"a": ["b"]}, "mut {[string]}").fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_asserted_type_does_not_match,
+        r#"
+        assert_type([["a"]], "[[integer]]");
+        "#
+    );
+
+    nash_test!(
+        should_error_when_asserted_type_is_not_valid,
+        r#"
+        assert_type(1, "[integer");
+        "#
+    );
+
+    nash_test!(
+        should_split_string_into_grapheme_characters,
+        r#"
+        out("abc".chars().fmt());
+        out("café 👍🏽!".chars().fmt());
+        out("café 👍🏽!".chars().len().fmt());
+        "#
+    );
+
+    nash_test!(
+        should_split_string_at_character_index,
+        r#"
+        out("hello".split_at(2).fmt());
+        out("hello".split_at(0).fmt());
+        out("hello".split_at(5).fmt());
+        out("naïve 👍🏽 text".split_at(7).fmt());
+        out("hello".split_at(6).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_interpret_escape_sequences_in_strings,
+        r#"
+        out("a\tb");
+        out("line\nbreak");
+        out("carriage\rreturn".len().fmt());
+        out("null\0char".len().fmt());
+        out("back\\slash");
+        out("\"quoted\"");
+        out("\${not_a_variable}");
+        out("\u{1F600} \u{e9} \u{41}");
+        "#
+    );
+
+    nash_test!(
+        should_error_on_unknown_escape_sequence,
+        r#"
+        out("\q");
+        "#
+    );
+
+    nash_test!(
+        should_error_on_invalid_unicode_escape,
+        r#"
+        out("\u{XYZ}");
+        "#
+    );
+
+    nash_test!(
+        should_error_on_unicode_escape_outside_valid_range,
+        r#"
+        out("\u{D800}");
+        "#
+    );
+
+    nash_test!(
+        should_error_on_unclosed_unicode_escape,
+        r#"
+        out("\u{41");
+        "#
+    );
+
+    nash_test!(
+        should_find_positions_of_value_in_array,
+        r#"
+        var values = [3, 1, 3, 2, 3];
+        out(values.positions(4).fmt());
+        out(values.positions(2).fmt());
+        out(values.positions(3).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_find_positions_matching_predicate,
+        r#"
+        func is_long(value: string): boolean {
+            return value.len() > 3;
+        }
+
+        out(["a", "b"].positions_where(is_long).fmt());
+        out(["a", "long", "b"].positions_where(is_long).fmt());
+        out(["apple", "b", "banana", "cherry"].positions_where(is_long).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_not_process_escapes_or_templates_in_raw_strings,
+        r#"
+        var r = "variable";
+        out(r"C:\Users\${not_a_var}");
+        out(r"\d+\.\d+");
+        out((r"" == "").fmt());
+        out(r);
+        "#
+    );
+
+    nash_test!(
+        should_allow_raw_strings_inside_templates,
+        r#"
+        out("path: ${r"C:\temp"}");
+        "#
+    );
+
+    nash_test!(
+        should_coalesce_to_first_non_null_value,
+        r#"
+        var values = parse_json("[null, \"first\", 2]").as_array();
+        out(to_json(coalesce(values[1], values[0], values[2])));
+        out(to_json(coalesce(values[0], values[2], values[1])));
+        out(to_json(coalesce(values[0], values[0], values[1])));
+        out(to_json(coalesce(values[2])));
+        out(coalesce(values[0], values[0]).is_null().fmt());
+        "#
+    );
+
+    nash_test!(
+        should_store_json_nulls_in_variables,
+        r#"
+        var value = parse_json("null");
+        out(value.is_null().fmt());
+        out(value.fmt());
+        out(to_json(value));
+        out(to_json([value, parse_json("1")]));
+        out(to_json({ "a": value }));
+        out(to_json(default(value, parse_json("2"))));
+        out((value == parse_json("null")).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_coalescing_values_that_are_not_json,
+        r#"
+        out(coalesce("a", "b"));
+        "#
+    );
+
+    nash_test!(
+        should_format_non_string_expressions_in_templates,
+        r#"
+        func double(value: integer): integer {
+            return value * 2;
+        }
+
+        var a = 1;
+        var b = 2;
+        out("${a + b} ${double(b)} ${[a, b]} ${(a, "b")} ${a == b} ${"as is"}");
+        "#
+    );
+
+    nash_test!(
+        should_error_on_void_expression_in_template,
+        r#"
+        out("${out("inner")}");
+        "#
+    );
+
+    nash_test!(
+        should_error_on_unterminated_template_expression,
+        r#"
+        out("sum: ${1 + 2");
+        "#
+    );
+
+    nash_test!(
+        should_error_on_template_expression_unterminated_at_end_of_file,
+        r#"
+        out("sum: ${1 + 2"#
+    );
+
+    nash_test!(
+        should_exit_from_inside_helper_function,
+        r#"
+        func die() {
+            exit 2;
+        }
+
+        func main() {
+            out("before");
+            die();
+            out("after");
+        }
+
+        main();
+        out("not printed");
+        "#
+    );
+
+    nash_test!(
+        should_wrap_text_at_word_boundaries,
+        r#"
+        var text = "The quick brown fox jumps over the lazy dog.
+
+Second   paragraph with some extra    spaces.";
+        out(text.wrap(16));
+        out(("a".repeat(20) + " short words").wrap(10));
+        out("日本語 日本語 日本語".wrap(14));
+        "#
+    );
+
+    nash_test!(
+        should_error_when_wrapping_with_non_positive_width,
+        r#"
+        out("text".wrap(0));
+        "#
+    );
+
+    nash_test!(
+        should_pass_assertions_that_hold,
+        r#"
+        assert(1 < 2);
+        assert_eq(1 + 1, 2);
+        assert_eq("a" + "b", "ab");
+        assert_eq([1, 2], [1, 2]);
+        assert_eq({ "key": (1, true) }, { "key": (1, true) });
+        out("passed");
+        "#
+    );
+
+    nash_test!(
+        should_fail_assertion_that_is_false,
+        r#"
+        assert(1 > 2);
+        out("not printed");
+        "#
+    );
+
+    nash_test!(
+        should_fail_assert_eq_with_both_values_formatted,
+        r#"
+        assert_eq([1, 2], [2, 1]);
+        out("not printed");
+        "#
+    );
+
+    nash_test!(
+        should_not_allow_assert_eq_on_different_types,
+        r#"
+        assert_eq(1, "1");
+        "#
+    );
+
+    nash_test!(
+        should_auto_format_values_in_templates,
+        r#"
+        var count = 3;
+        var done = true;
+        var name = "nash";
+        out("count: ${count}, done: ${done}, name: ${name}");
+        out("${["a", "b"]} ${(count, name)} ${name.str() == "${name}"}");
+        "#
+    );
+
+    nash_test!(
+        should_use_fallback_when_value_is_void,
+        r#"
+        func nothing() {
+            out("called nothing");
+        }
+
+        func answer(): integer {
+            return 42;
+        }
+
+        out(default(nothing(), 1).fmt());
+        out(default(answer(), 1).fmt());
+        out(default(default(integer), 1).fmt());
+
+        var values = parse_json("[null, 2]").as_array();
+        out(to_json(default(values[0], values[1])));
+        out(to_json(default(values[1], parse_json("3"))));
+        "#
+    );
+
+    nash_test!(
+        should_error_when_default_fallback_has_different_type,
+        r#"
+        out(default(1, "a"));
+        "#
+    );
+
+    nash_test!(
+        should_support_char_literals,
+        r#"
+        func show(letter: char) {
+            out(letter.fmt());
+            out("letter: ${letter}");
+        }
+        show('a');
+        out(('\n' == char_from_int(10)).fmt());
+        out(('\'' == char_from_int(39)).fmt());
+        out('\''.fmt());
+        out('\u{1F600}'.fmt());
+        "#
+    );
+
+    nash_test!(
+        should_convert_chars_to_and_from_integers,
+        r#"
+        out(char_to_int('A').fmt());
+        out(char_from_int(97).fmt());
+        out(char_to_int(char_from_int(128512)).fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_converting_surrogate_to_char,
+        r#"
+        char_from_int(55296);
+        "#
+    );
+
+    nash_test!(
+        should_error_when_converting_negative_integer_to_char,
+        r#"
+        char_from_int(-1);
+        "#
+    );
+
+    nash_test!(
+        should_error_when_char_literal_has_multiple_characters,
+        r#"
+        var letters = 'ab';
+        "#
+    );
+
+    nash_test!(
+        should_destructure_tuple_returned_from_function,
+        r#"
+        func divide(value: integer, divisor: integer): (integer, integer) {
+            return (value / divisor, value % divisor);
+        }
+
+        var (quotient, remainder) = divide(17, 5);
+        out("${quotient} remainder ${remainder}");
+        "#
+    );
+
+    nash_test!(
+        should_assign_tuple_to_existing_variables,
+        r#"
+        var mut name = "";
+        var mut count = 0;
+        (name, count) = ("apples", 3);
+        out("${name}: ${count}");
+        "#
+    );
+
+    nash_test!(
+        should_error_when_tuple_declaration_has_wrong_number_of_names,
+        r#"
+        var (first, second) = (1, 2, 3);
+        "#
+    );
+
+    nash_test!(
+        should_error_when_tuple_assignment_has_wrong_element_type,
+        r#"
+        var mut name = "";
+        var mut count = 0;
+        (name, count) = (3, "apples");
+        "#
+    );
+
+    nash_test!(
+        should_error_when_tuple_assignment_value_is_not_tuple,
+        r#"
+        var (first, second) = 1;
+        "#
+    );
+
+    nash_test!(
+        should_reassign_variables_declared_with_tuple_assignment,
+        r#"
+        var mut (a, b) = (1, "two");
+        out("${a} ${b}");
+        (a, b) = (3, "four");
+        out((a + 1).fmt());
+        out(b.to_upper());
+        "#
+    );
+
+    nash_test!(
+        should_not_allow_reassigning_immutable_tuple_declaration,
+        r#"
+        var (a, b) = (1, 2);
+        a = 3;
+        "#
+    );
+
+    nash_test!(
+        should_ignore_underscore_in_tuple_assignment,
+        r#"
+        var mut count = 0;
+        (count, _) = (3, "ignored");
+        out(count.fmt());
+        "#
+    );
+
+    nash_test!(
+        should_run_do_while_body_once_when_condition_is_false,
+        r#"
+        do {
+            out("ran");
+        } while false;
+        "#
+    );
+
+    nash_test!(
+        should_repeat_do_while_loop_until_condition_is_false,
+        r#"
+        var mut attempts = 0;
+        do {
+            attempts += 1;
+            if attempts == 2 {
+                continue;
+            };
+            out(attempts.fmt());
+        } while attempts < 4;
+        "#
+    );
+
+    nash_test!(
+        should_break_from_labelled_do_while_loop,
+        r#"
+        var mut count = 0;
+        outer: do {
+            for item in [1, 2, 3] {
+                count += item;
+                if count > 4 {
+                    break outer;
+                };
+            };
+        } while true;
+        out(count.fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_do_while_condition_is_not_boolean,
+        r#"
+        do {} while 1;
+        "#
+    );
+
+    nash_test!(
+        should_expose_index_in_for_loop,
+        r#"
+        for (index, item) in ["a", "b", "c"] {
+            out("${index}: ${item}");
+        };
+        for item in ["d"] {
+            out(item);
+        };
+        "#
+    );
+
+    nash_test!(
+        should_error_when_for_loop_index_is_used_as_string,
+        r#"
+        for (index, item) in ["a"] {
+            out(index);
+        };
+        "#
+    );
+
+    nash_test!(
+        should_sum_and_find_min_and_max_of_integer_arrays,
+        r#"
+        var values = [3, -7, 12, 5];
+        out(values.sum().fmt());
+        out(values.min().fmt());
+        out(values.max().fmt());
+        out([42].min().fmt());
+        out([42].max().fmt());
+        out([42].sum().fmt());
+        out(default([integer]).sum().fmt());
+        "#
+    );
+
+    nash_test!(
+        should_error_when_getting_min_of_empty_array,
+        r#"
+        default([integer]).min();
+        "#
+    );
+
+    nash_test!(
+        should_error_when_getting_max_of_empty_array,
+        r#"
+        default([integer]).max();
+        "#
+    );
+
+    nash_test!(
+        should_error_when_sum_overflows,
+        r#"
+        [2147483647, 1].sum();
+        "#
+    );
+
+    nash_test!(
+        should_error_when_summing_non_integer_array,
+        r#"
+        ["a", "b"].sum();
+        "#
+    );
+
+    nash_test!(
+        should_return_trailing_expression_of_function,
+        r#"
+        func double(value: integer): integer {
+            value * 2
+        }
+        func log(values: mut [integer]) {
+            values.pop()
+        }
+        out(double(4).fmt());
+        log(mut [1]);
+        "#
+    );
+
+    nash_test!(
+        should_error_when_trailing_expression_does_not_match_return_type,
+        r#"
+        func name(): integer {
+            "nash"
+        }
+        "#
+    );
+
+    struct ExecCounter {
+        count: usize,
+    }
+
+    impl ast::Visitor for ExecCounter {
+        fn visit_expression(&mut self, expression: &ast::BaseExpression) {
+            if let ast::BaseExpression::PipelineExpression(_) = expression {
+                self.count += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn should_count_exec_nodes_using_visitor() {
+        let root = ast::parse(
+            r#"
+            func build() {
+                exec `make`;
+            }
+            exec `echo start`;
+            if true {
+                var output = exec `ls` => `wc -l`;
+            };
+            for file in ["a", "b"] {
+                out("${exec `cat`}");
+            };
+            "#,
+        )
+        .unwrap();
+
+        let mut counter = ExecCounter { count: 0 };
+        ast::walk(&root, &mut counter);
+        assert_eq!(counter.count, 4);
+    }
+
+    fn run_cat_with_trailing_newlines(options: ExecutorOptions) -> Option<String> {
+        let executor = SystemExecutor::new(options);
+        let pipeline = Pipeline::new(
+            vec!["cat".into()],
+            Some(PipelineSource::Literal("hello\n\n".to_owned())),
+            None,
+        );
+
+        executor.run_pipeline(&pipeline).unwrap().stdout
+    }
+
+    #[test]
+    fn should_preserve_trailing_newlines_in_command_output() {
+        let output = run_cat_with_trailing_newlines(ExecutorOptions::default());
+        assert_eq!(output.as_deref(), Some("hello\n\n"));
+    }
+
+    #[test]
+    fn should_trim_command_output_when_enabled() {
+        let options = ExecutorOptions::builder().trim_command_output(true).build();
+
+        let output = run_cat_with_trailing_newlines(options);
+        assert_eq!(output.as_deref(), Some("hello"));
+    }
+
+    #[test]
+    fn should_serialize_parse_errors_as_json() {
+        let options = ExecutorOptions::builder().json_errors(true).build();
+        let mut executor = SystemExecutor::new(options);
+
+        let Err(err) = nash::execute(&mut "var = 1;".as_bytes(), &mut executor) else {
+            panic!("Script should fail to parse");
+        };
+        let json = err.to_json();
+
+        assert!(json.starts_with("{\"ParserError\":"), "{json}");
+        assert!(json.contains("\"message\":"), "{json}");
+        assert!(json.contains("\"start\":4"), "{json}");
+        assert!(json.contains("\"end\":5"), "{json}");
+    }
+
+    #[test]
+    fn should_report_line_and_column_of_parser_errors() {
+        let script = "var x = 1;\nvar y = \"héllo\";\n  var = 3;\n";
+        let mut executor = MockExecutor::new("", MockPipelineExecutor::new());
+
+        let Err(NashError::ParserError(err)) = nash::execute(&mut script.as_bytes(), &mut executor)
+        else {
+            panic!("Script should fail to parse");
+        };
+
+        assert_eq!(err.line_column(script), Some((3, 7)));
+    }
+
+    #[test]
+    fn should_parse_script_into_serializable_syntax_tree() {
+        let tree = nash::parse_script(r#"var greeting = "hello"; out(greeting);"#).unwrap();
+        assert_yaml_snapshot!(tree.root());
+    }
+
+    #[test]
+    fn should_return_parser_error_when_parsing_script() {
+        let Err(NashError::ParserError(err)) = nash::parse_script("var = 1;") else {
+            panic!("Script should fail to parse");
+        };
+        assert_eq!(err.start, Some(4));
+    }
+
+    #[test]
+    fn should_check_script_without_running_it() {
+        let script = r#"exec `rm -rf /tmp/should_not_run`; out("hi");"#;
+        assert!(nash::check(&mut script.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn should_report_every_type_error_when_checking() {
+        let script = r#"
+        func broken(): integer { return "a"; }
+        var value = 1 + "a";
+        out(missing);
+        out("fine");
+        "#;
+
+        let errors = nash::check(&mut script.as_bytes()).unwrap_err();
+        let messages = errors.iter().map(|err| err.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "Function has a declared return type of integer, but return statement got a type of string",
+                "Invalid operator expression Integer Addition String.",
+                "Variable 'missing' has not been declared",
+            ]
+        );
+        assert!(errors.iter().all(|err| err.exit_code() == 103));
+    }
+
+    #[test]
+    fn should_stop_at_parser_error_when_checking() {
+        let errors = nash::check(&mut "out(1);\nvar = 1; out(missing);".as_bytes()).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].exit_code(), 102);
+
+        let NashError::ParserError(err) = &errors[0] else {
+            panic!("Expected a parser error");
+        };
+        assert_eq!((err.line, err.column), (Some(2), Some(5)));
+        assert!(errors[0].to_json().contains(r#""line":2,"column":5"#));
+    }
+
+    #[test]
+    fn should_glob_hidden_files_and_relative_paths() {
+        let test_dir = std::env::current_dir().unwrap().join("target/glob_test");
+        let _ = std::fs::remove_dir_all(&test_dir);
+        std::fs::create_dir_all(&test_dir).unwrap();
+        std::fs::write(test_dir.join("visible.txt"), "").unwrap();
+        std::fs::write(test_dir.join(".hidden.txt"), "").unwrap();
+
+        let pattern = test_dir.join("*.txt").to_string_lossy().into_owned();
+        let script = format!(
+            r#"
+            out(glob("{pattern}", false, true).fmt());
+            out(glob("{pattern}", true, true).fmt());
+            "#
+        );
+        let output = run_code(&script, "", |_| {});
+
+        assert_eq!(output.error, None);
+        assert_eq!(
+            output.stdout,
+            "[\"target/glob_test/visible.txt\"]\n\
+            [\"target/glob_test/.hidden.txt\",\"target/glob_test/visible.txt\"]\n"
+        );
+    }
+
+    #[test]
+    fn should_kill_commands_that_exceed_timeout() {
+        let options = ExecutorOptions::builder()
+            .command_timeout(std::time::Duration::from_millis(100))
+            .build();
+        let executor = SystemExecutor::new(options);
+
+        let started = std::time::Instant::now();
+        let output = executor
+            .run_pipeline(&Pipeline::new(
+                vec![CommandDefinition::new(
+                    "sleep".to_owned(),
+                    vec!["5".to_owned()],
+                    false,
+                )],
+                None,
+                None,
+            ))
+            .unwrap();
+
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert!(output.command_outputs[0].timed_out);
+        assert_eq!(
+            output.command_outputs[0].exit_code,
+            CommandOutput::TIMED_OUT_EXIT_CODE
+        );
+    }
+
+    #[test]
+    fn should_not_kill_commands_within_timeout() {
+        let options = ExecutorOptions::builder()
+            .command_timeout(std::time::Duration::from_secs(5))
+            .build();
+        let output = run_cat_with_trailing_newlines(options);
+        assert_eq!(output.as_deref(), Some("hello\n\n"));
+    }
+
+    fn print_to_stdout_and_stderr() -> CommandDefinition {
+        CommandDefinition::new(
+            "sh".to_owned(),
+            vec!["-c".to_owned(), "echo out; echo err 1>&2".to_owned()],
+            false,
+        )
+        .with_merged_stderr()
+    }
+
+    #[test]
+    fn should_merge_stderr_into_piped_command() {
+        let executor = SystemExecutor::new(ExecutorOptions::default());
+        let output = executor
+            .run_pipeline(&Pipeline::new(
+                vec![print_to_stdout_and_stderr(), "cat".into()],
+                None,
+                None,
+            ))
+            .unwrap();
+
+        assert_eq!(output.stdout.as_deref(), Some("out\nerr\n"));
+    }
+
+    #[test]
+    fn should_merge_stderr_into_file_destination() {
+        let path = std::env::current_dir()
+            .unwrap()
+            .join("target/merge_stderr_test.txt");
+        let _ = std::fs::remove_file(&path);
+
+        let executor = SystemExecutor::new(ExecutorOptions::default());
+        executor
+            .run_pipeline(&Pipeline::new(
+                vec![print_to_stdout_and_stderr()],
+                None,
+                Some(PipelineDestination::FileWrite(
+                    path.to_string_lossy().into_owned(),
+                )),
+            ))
+            .unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "out\nerr\n");
+    }
+
+    #[test]
+    fn should_write_profile_summary_to_stderr_when_enabled() {
+        let mut mock_pipeline_executor = MockPipelineExecutor::new();
+        mock_pipeline_executor
+            .expect_run_pipeline()
+            .times(2)
+            .returning(|_| Ok(pipeline_success("", 1)));
+
+        let mut executor = MockExecutor::new("", mock_pipeline_executor);
+        executor.options = ExecutorOptions::builder().profile(true).build();
+        nash::execute(
+            &mut "exec `true`; exec `true`; err(\"done\");".as_bytes(),
+            &mut executor,
+        )
+        .unwrap();
+
+        let stderr = str::from_utf8(executor.stderr()).unwrap().to_owned();
+        let lines = stderr.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5, "{stderr}");
+        assert_eq!(lines[0], "done");
+        assert_eq!(lines[1], "Profile:");
+        assert!(lines[2].starts_with("  Total: "), "{stderr}");
+        assert!(lines[3].starts_with("  Commands: "), "{stderr}");
+        assert!(lines[3].ends_with(" (2 pipelines)"), "{stderr}");
+        assert!(lines[4].starts_with("  Interpreter: "), "{stderr}");
+    }
+
+    #[test]
+    fn should_not_write_profile_summary_by_default() {
+        let output = run_code("exec `true`;", "", |executor| {
+            executor
+                .expect_run_pipeline()
+                .returning(|_| Ok(pipeline_success("", 1)));
+        });
+
+        assert_eq!(output.error, None);
+        assert_eq!(output.stderr, "");
+    }
+
+    #[test]
+    fn should_use_defaults_for_options_not_set_on_builder() {
+        let options = ExecutorOptions::builder().build();
+        assert_eq!(options, ExecutorOptions::default());
+        assert_eq!(options.max_call_stack_depth, 64);
+        assert!(!options.trim_command_output);
+        assert!(!options.json_errors);
+        assert!(!options.skip_invalid_glob_paths);
+        assert_eq!(options.command_timeout, None);
+        assert!(!options.profile);
+    }
+
+    #[test]
+    fn should_construct_options_using_builder() {
+        let options = ExecutorOptions::builder()
+            .max_call_stack_depth(32)
+            .trim_command_output(true)
+            .command_timeout(std::time::Duration::from_secs(10))
+            .build();
+
+        assert_eq!(options.max_call_stack_depth, 32);
+        assert!(options.trim_command_output);
+        assert!(!options.json_errors);
+        assert_eq!(
+            options.command_timeout,
+            Some(std::time::Duration::from_secs(10))
+        );
+    }
+
+    #[test]
+    fn should_write_tee_output_to_file_and_return_it() {
+        let path = std::env::current_dir().unwrap().join("target/tee_test.txt");
+        let _ = std::fs::remove_file(&path);
+
+        let executor = SystemExecutor::new(ExecutorOptions::default());
+        let output = executor
+            .run_pipeline(&Pipeline::new(
+                vec![CommandDefinition::new(
+                    "echo".to_owned(),
+                    vec!["hello".to_owned()],
+                    false,
+                )],
+                None,
+                Some(PipelineDestination::Tee(
+                    path.to_string_lossy().into_owned(),
+                )),
+            ))
+            .unwrap();
+
+        assert_eq!(output.stdout.as_deref(), Some("hello\n"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello\n");
+    }
+
+    #[test]
+    fn should_capture_stdout_of_intermediate_command() {
+        let executor = SystemExecutor::new(ExecutorOptions::default());
+        let output = executor
+            .run_pipeline(&Pipeline::new(
+                vec![
+                    CommandDefinition::new("echo".to_owned(), vec!["hello".to_owned()], false)
+                        .with_captured_stdout(),
+                    CommandDefinition::new(
+                        "tr".to_owned(),
+                        vec!["a-z".to_owned(), "A-Z".to_owned()],
+                        false,
+                    ),
+                ],
+                None,
+                None,
+            ))
+            .unwrap();
+
+        assert_eq!(output.stdout.as_deref(), Some("HELLO\n"));
+        assert_eq!(output.command_outputs[0].stdout.as_deref(), Some("hello\n"));
+        assert_eq!(output.command_outputs[1].stdout, None);
+    }
+
+    #[test]
+    fn should_pass_substituted_command_output_as_file() {
+        let executor = SystemExecutor::new(ExecutorOptions::default());
+        let output = executor
+            .run_pipeline(&Pipeline::new(
+                vec![
+                    CommandDefinition::from("cat").with_substitution(CommandDefinition::new(
+                        "echo".to_owned(),
+                        vec!["substituted".to_owned()],
+                        false,
+                    )),
+                ],
+                None,
+                None,
+            ))
+            .unwrap();
+
+        assert_eq!(output.stdout.as_deref(), Some("substituted\n"));
+    }
+
+    #[test]
+    fn should_error_when_substituted_command_fails() {
+        let executor = SystemExecutor::new(ExecutorOptions::default());
+        let result = executor.run_pipeline(&Pipeline::new(
+            vec![CommandDefinition::from("cat").with_substitution("false".into())],
+            None,
+            None,
+        ));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_kill_substituted_commands_that_exceed_timeout() {
+        let options = ExecutorOptions::builder()
+            .command_timeout(std::time::Duration::from_millis(100))
+            .build();
+        let executor = SystemExecutor::new(options);
+
+        let started = std::time::Instant::now();
+        let result = executor.run_pipeline(&Pipeline::new(
+            vec![
+                CommandDefinition::from("cat").with_substitution(CommandDefinition::new(
+                    "sleep".to_owned(),
+                    vec!["5".to_owned()],
+                    false,
+                )),
+            ],
+            None,
+            None,
+        ));
+
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert!(result.is_err());
+    }
 }